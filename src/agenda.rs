@@ -0,0 +1,207 @@
+//! Open tasks of a project tree, grouped by when they are due.
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::{Project, Task};
+
+/// When a task is due, relative to today
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bucket {
+    Overdue,
+    Today,
+    /// After today, up to and including sunday
+    ThisWeek,
+    Later,
+    NoDate,
+}
+
+impl Bucket {
+    #[must_use]
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Overdue => "Overdue",
+            Self::Today => "Today",
+            Self::ThisWeek => "This week",
+            Self::Later => "Later",
+            Self::NoDate => "No date",
+        }
+    }
+
+    pub(crate) fn of(due: Option<NaiveDate>, today: NaiveDate) -> Self {
+        let Some(due) = due else {
+            return Self::NoDate;
+        };
+
+        let sunday = today + Duration::days(6 - i64::from(today.weekday().num_days_from_monday()));
+
+        if due < today {
+            Self::Overdue
+        } else if due == today {
+            Self::Today
+        } else if due <= sunday {
+            Self::ThisWeek
+        } else {
+            Self::Later
+        }
+    }
+}
+
+/// An open task together with the name of its project
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub project: &'a str,
+    pub task: &'a Task,
+}
+
+/// Groups the open tasks of `project` and all of its children by due date.
+/// Empty groups are left out. Within a group, tasks are sorted by due date,
+/// project and index.
+///
+/// With `days`, only tasks due within that many days from `today` are
+/// included, overdue ones always are.
+#[must_use]
+pub fn collect(
+    project: &Project,
+    today: NaiveDate,
+    days: Option<u32>,
+) -> Vec<(Bucket, Vec<Entry<'_>>)> {
+    collect_all([project], today, days)
+}
+
+/// Like [`collect`], but for the trees of several root projects at once
+#[must_use]
+pub fn collect_all<'a>(
+    projects: impl IntoIterator<Item = &'a Project>,
+    today: NaiveDate,
+    days: Option<u32>,
+) -> Vec<(Bucket, Vec<Entry<'a>>)> {
+    let mut entries = Vec::new();
+    for project in projects {
+        collect_rec(project, &mut entries);
+    }
+
+    if let Some(days) = days {
+        let end = today + Duration::days(i64::from(days));
+        entries.retain(|e| e.task.due.is_some_and(|due| due <= end));
+    }
+
+    entries.sort_by(|a, b| {
+        a.task
+            .due
+            .is_none()
+            .cmp(&b.task.due.is_none())
+            .then(a.task.due.cmp(&b.task.due))
+            .then(a.project.cmp(b.project))
+            .then(a.task.index.cmp(&b.task.index))
+    });
+
+    let mut groups: Vec<(Bucket, Vec<Entry>)> = Vec::new();
+    for entry in entries {
+        let bucket = Bucket::of(entry.task.due, today);
+        match groups.last_mut() {
+            Some((last, group)) if *last == bucket => group.push(entry),
+            _ => groups.push((bucket, vec![entry])),
+        }
+    }
+
+    groups
+}
+
+fn collect_rec<'a>(project: &'a Project, entries: &mut Vec<Entry<'a>>) {
+    entries.extend(
+        project
+            .data
+            .tasks
+            .iter()
+            .filter(|t| !t.completed)
+            .map(|task| Entry {
+                project: &project.data.name,
+                task,
+            }),
+    );
+
+    for child in project.get_children() {
+        collect_rec(child, entries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{collect, collect_all, Bucket};
+    use crate::Project;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    #[test]
+    fn buckets() {
+        let mut project = Project::new(PathBuf::new(), 0, "root".to_string(), false);
+        project.add(String::from("no date"), false).unwrap();
+        project.add(String::from("today"), false).unwrap().due = Some(date(14));
+        project.add(String::from("done"), true).unwrap().due = Some(date(1));
+        project.add(String::from("later"), false).unwrap().due = Some(date(30));
+
+        let mut child = Project::new(PathBuf::new(), 1, "child".to_string(), true);
+        child.add(String::from("overdue"), false).unwrap().due = Some(date(2));
+        child.add(String::from("sunday"), false).unwrap().due = Some(date(18));
+        child.add(String::from("monday"), false).unwrap().due = Some(date(19));
+        project.attach_child(child);
+
+        // a wednesday
+        let today = date(14);
+        let groups: Vec<_> = collect(&project, today, None)
+            .into_iter()
+            .map(|(bucket, entries)| {
+                let entries: Vec<_> = entries
+                    .iter()
+                    .map(|e| format!("{}:{}", e.project, e.task.desc))
+                    .collect();
+                (bucket, entries)
+            })
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![
+                (Bucket::Overdue, vec![String::from("child:overdue")]),
+                (Bucket::Today, vec![String::from("root:today")]),
+                (Bucket::ThisWeek, vec![String::from("child:sunday")]),
+                (
+                    Bucket::Later,
+                    vec![String::from("child:monday"), String::from("root:later")]
+                ),
+                (Bucket::NoDate, vec![String::from("root:no date")]),
+            ]
+        );
+
+        let within: Vec<_> = collect(&project, today, Some(4))
+            .iter()
+            .map(|(bucket, entries)| (*bucket, entries.len()))
+            .collect();
+        assert_eq!(
+            within,
+            vec![
+                (Bucket::Overdue, 1),
+                (Bucket::Today, 1),
+                (Bucket::ThisWeek, 1)
+            ]
+        );
+
+        let mut other = Project::new(PathBuf::new(), 0, "other".to_string(), false);
+        other.add(String::from("elsewhere"), false).unwrap().due = Some(date(14));
+        let today_group: Vec<_> = collect_all([&project, &other], today, None)[1]
+            .1
+            .iter()
+            .map(|e| format!("{}:{}", e.project, e.task.desc))
+            .collect();
+        assert_eq!(
+            today_group,
+            vec![String::from("other:elsewhere"), String::from("root:today")]
+        );
+    }
+}
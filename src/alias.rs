@@ -0,0 +1,200 @@
+//! Aliases from the `[alias]` section of the user configuration, like
+//! `wip = "add --tag wip"`. They are expanded before the command line is
+//! parsed and can refer to other aliases, but not replace built-in commands.
+
+use std::{collections::BTreeMap, ffi::OsString};
+
+use toml::value::Table;
+
+use crate::app::COMMANDS;
+
+#[derive(Debug, Default)]
+pub struct Aliases {
+    aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Aliases {
+    /// Reads the aliases from the `[alias]` section. An alias is either a
+    /// string, split into words like a shell would, or a list of words.
+    pub fn from_config(section: Option<&Table>) -> Result<Self, String> {
+        let mut aliases = BTreeMap::new();
+
+        for (name, value) in section.into_iter().flatten() {
+            let words = match value {
+                toml::Value::String(s) => split_words(s)
+                    .ok_or_else(|| format!("alias `{name}` has an unterminated quote"))?,
+                toml::Value::Array(words) => words
+                    .iter()
+                    .map(|w| w.as_str().map(String::from))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| format!("alias `{name}` must only contain strings"))?,
+                _ => {
+                    return Err(format!(
+                        "alias `{name}` must be a string or a list of strings"
+                    ))
+                }
+            };
+
+            aliases.insert(name.clone(), words);
+        }
+
+        Ok(Self { aliases })
+    }
+
+    /// The aliases and what they expand to, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.aliases
+            .iter()
+            .filter(|(name, _)| !COMMANDS.contains(&name.as_str()))
+            .map(|(name, words)| (name.as_str(), words.as_slice()))
+    }
+
+    /// Replaces the subcommand in `args` as long as it is an alias. With
+    /// `completing`, a subcommand that is the last word is left alone, it is
+    /// still being typed.
+    pub fn expand(
+        &self,
+        mut args: Vec<OsString>,
+        completing: bool,
+    ) -> Result<Vec<OsString>, String> {
+        let mut seen = Vec::new();
+
+        loop {
+            let Some(at) = subcommand(&args) else {
+                return Ok(args);
+            };
+            if completing && at + 1 == args.len() {
+                return Ok(args);
+            }
+
+            let Some(name) = args[at].to_str() else {
+                return Ok(args);
+            };
+            let Some(words) = self.aliases.get(name) else {
+                return Ok(args);
+            };
+            if COMMANDS.contains(&name) {
+                return Ok(args);
+            }
+
+            if seen.iter().any(|s| s == name) {
+                seen.push(name.to_owned());
+                return Err(format!("alias loop: {}", seen.join(" -> ")));
+            }
+            seen.push(name.to_owned());
+
+            args.splice(at..=at, words.iter().map(OsString::from));
+        }
+    }
+}
+
+/// The position of the subcommand, after the options for all of tutel
+fn subcommand(args: &[OsString]) -> Option<usize> {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.to_str() {
+            // takes a value
+            Some("--color") => i += 2,
+            Some(a) if a.starts_with('-') => i += 1,
+            _ => return Some(i),
+        }
+    }
+
+    None
+}
+
+/// Splits `s` at whitespace, except within single or double quotes. Returns
+/// `None` if a quote is not closed.
+fn split_words(s: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+
+    for c in s.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return None;
+    }
+    words.extend(word);
+
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::{split_words, Aliases};
+
+    fn aliases(config: &str) -> Aliases {
+        let table: toml::value::Table = toml::from_str(config).unwrap();
+        Aliases::from_config(Some(&table)).unwrap()
+    }
+
+    fn expand(aliases: &Aliases, args: &[&str]) -> Result<Vec<OsString>, String> {
+        aliases.expand(args.iter().map(OsString::from).collect(), false)
+    }
+
+    fn words(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            split_words(r#"add  -t 'in progress' "" x"#).unwrap(),
+            ["add", "-t", "in progress", "", "x"]
+        );
+        assert_eq!(split_words("add 'oops"), None);
+    }
+
+    #[test]
+    fn expansion() {
+        let aliases = aliases(
+            r#"
+            wip = "add --tag wip"
+            w = "wip -p 1"
+            list = ["show", "--ids"]
+            add = "rm"
+            loop1 = "loop2"
+            loop2 = "loop1 x"
+            "#,
+        );
+
+        assert_eq!(
+            expand(&aliases, &["--color", "never", "w", "fix it"]),
+            Ok(words(&[
+                "--color", "never", "add", "--tag", "wip", "-p", "1", "fix it"
+            ]))
+        );
+        assert_eq!(expand(&aliases, &["list"]), Ok(words(&["show", "--ids"])));
+        // built-in commands can't be replaced, arguments are not expanded
+        assert_eq!(
+            expand(&aliases, &["add", "wip"]),
+            Ok(words(&["add", "wip"]))
+        );
+        assert_eq!(expand(&aliases, &[]), Ok(Vec::new()));
+        assert_eq!(
+            expand(&aliases, &["loop1"]),
+            Err(String::from("alias loop: loop1 -> loop2 -> loop1"))
+        );
+
+        // still typing
+        assert_eq!(aliases.expand(words(&["wip"]), true), Ok(words(&["wip"])));
+        assert_eq!(
+            aliases.expand(words(&["wip", ""]), true),
+            Ok(words(&["add", "--tag", "wip", ""]))
+        );
+    }
+}
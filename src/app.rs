@@ -1,32 +1,191 @@
-use bpaf::{construct, env, long, positional, short, OptionParser, Parser};
+use std::{ffi::OsString, path::PathBuf, str::FromStr};
+
+use bpaf::{
+    any, construct, env, long, positional, pure, short, Args, OptionParser, ParseFailure, Parser,
+};
+use chrono::{Duration, Local, NaiveDate};
+
+use tutel::{estimate::Estimate, SortKey, TaskRef};
+
+use crate::{alias::Aliases, external, theme::ColorChoice};
 
 /// Indicates what Task(s) to select
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TaskSelector {
-    Indexed(Vec<usize>),
+    Indexed(Vec<TaskRef>),
     All,
     Completed,
 }
 
+/// A file format tasks can be exported to or imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Ics,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "ics" | "ical" => Ok(Self::Ics),
+            _ => Err(format!("unknown format: {s}")),
+        }
+    }
+}
+
+/// Where to move a task to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveTo {
+    Top,
+    Bottom,
+    Before(TaskRef),
+}
+
+/// What to do with the archive of completed tasks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveAction {
+    /// Move completed tasks to the archive
+    Store,
+    List,
+    Search(String),
+    /// Move archived tasks back, selected by id prefix
+    Restore(Vec<String>),
+}
+
+/// Options for showing the todo list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShowOptions {
+    pub ids: bool,
+    /// Only list the open tasks that fit into this budget
+    pub budget: Option<Estimate>,
+}
+
+/// How a command that changes projects goes about it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Confirm {
+    /// Only print what would change
+    pub dry_run: bool,
+    /// Don't ask before bulk or destructive changes
+    pub yes: bool,
+}
+
 /// The command to execute
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
-    Show,
-    NewProject { name: Option<String>, force: bool },
-    AddTask { desc: String, completed: bool },
+    Show(ShowOptions),
+    NewProject {
+        name: Option<String>,
+        force: bool,
+    },
+    AddTask {
+        desc: String,
+        completed: bool,
+        due: Option<NaiveDate>,
+        tags: Vec<String>,
+        priority: Option<u8>,
+        estimate: Option<Estimate>,
+    },
+    /// Adds one task per line of a file or, without one, stdin
+    BulkAdd {
+        source: Option<PathBuf>,
+        metadata: bool,
+    },
     MarkCompletion(bool, TaskSelector),
     RemoveTask(TaskSelector),
-    EditTask(String, usize),
-    RemoveProject,
+    EditTask(String, TaskRef),
+    /// Sets or, without an estimate, clears the estimate of a task
+    EstimateTask(TaskRef, Option<Estimate>),
+    MoveTask(TaskRef, MoveTo),
+    SortTasks(SortKey),
+    /// Renames the nearest project
+    RenameProject(String),
+    /// Shows details about the nearest project
+    ProjectInfo,
+    /// Removes the nearest project file, with `recursive` also the ones above
+    RemoveProject {
+        recursive: bool,
+    },
+    Export {
+        format: Format,
+        indices: bool,
+    },
+    Import {
+        format: Format,
+        file: Option<PathBuf>,
+        headings: bool,
+    },
+    Scan,
+    Archive(ArchiveAction),
+    Stats {
+        json: bool,
+    },
+    Search {
+        pattern: String,
+        regex: bool,
+        ignore_case: bool,
+        /// Also search the projects listed in the config
+        all: bool,
+    },
+    Agenda {
+        days: Option<u32>,
+        /// Also include the projects listed in the config
+        all: bool,
+    },
+    StartTimer(TaskRef),
+    StopTimer,
+    Report {
+        week: bool,
+        csv: bool,
+    },
+    Watch {
+        poll: bool,
+    },
+    Prompt {
+        format: Option<String>,
+        timeout: u64,
+    },
+    Serve {
+        socket: PathBuf,
+    },
+    Web {
+        listen: String,
+    },
+    MergeDriver {
+        base: PathBuf,
+        ours: PathBuf,
+        theirs: PathBuf,
+    },
+    InstallMergeDriver,
+    /// Runs `tutel-<name>` from PATH, `help` lists them
+    External {
+        name: String,
+        args: Vec<OsString>,
+    },
 }
 
 #[derive(Clone)]
 pub struct App {
+    pub color: Option<ColorChoice>,
+    pub ascii: bool,
     pub cmd: Command,
+    pub confirm: Confirm,
 }
 
 fn parser() -> bpaf::OptionParser<App> {
+    parser_with(true)
+}
+
+/// With `external`, unknown subcommands are parsed as [`Command::External`]
+fn parser_with(external: bool) -> bpaf::OptionParser<App> {
     // Subcommands
+    let show_cmd = show_command()
+        .command("show")
+        .help("show the todo list")
+        .map(no_confirm);
+
     let new_cmd = new_project_command()
         .command("new")
         .help("create a new project");
@@ -48,34 +207,322 @@ fn parser() -> bpaf::OptionParser<App> {
         .short('e')
         .help("edit an existing task");
 
+    let estimate_cmd = estimate_command()
+        .command("estimate")
+        .help("set how much effort a task takes");
+
+    let move_cmd = move_command()
+        .command("move")
+        .help("change where a task is listed");
+
+    let sort_cmd = sort_command()
+        .command("sort")
+        .help("reorder the tasks of the project");
+
+    let rename_cmd = rename_command()
+        .command("rename")
+        .help("rename the nearest project");
+
+    let export_cmd = export_command()
+        .command("export")
+        .help("print the project in another format")
+        .map(no_confirm);
+
+    let import_cmd = import_command()
+        .command("import")
+        .help("add tasks from another format");
+
+    let scan_cmd = confirmed(pure(Command::Scan))
+        .to_options()
+        .descr("collect TODO, FIXME and HACK comments from the source code of the project")
+        .command("scan")
+        .help("collect TODO comments from the source code");
+
+    let archive_cmd = archive_command()
+        .command("archive")
+        .help("move completed tasks to the archive");
+
+    let search_cmd = search_command()
+        .command("search")
+        .help("search the tasks of the project tree")
+        .map(no_confirm);
+
+    let agenda_cmd = agenda_command()
+        .command("agenda")
+        .help("show open tasks grouped by due date")
+        .map(no_confirm);
+
+    let start_cmd = start_command()
+        .command("start")
+        .help("start working on a task");
+
+    let stop_cmd = confirmed(pure(Command::StopTimer))
+        .to_options()
+        .descr("stop the running timer and record the time on its task")
+        .command("stop")
+        .help("stop working on the current task");
+
+    let report_cmd = report_command()
+        .command("report")
+        .help("show the time tracked per task")
+        .map(no_confirm);
+
+    let stats_cmd = stats_command()
+        .command("stats")
+        .help("show statistics about the project")
+        .map(no_confirm);
+
+    let project_cmd = project_command()
+        .command("project")
+        .help("show details about the nearest project")
+        .map(no_confirm);
+
+    let watch_cmd = watch_command()
+        .command("watch")
+        .help("show the project and redraw it when it changes")
+        .map(no_confirm);
+
+    let prompt_cmd = prompt_command()
+        .command("prompt")
+        .help("print a short summary of the nearest project for the shell prompt")
+        .map(no_confirm);
+
+    let serve_cmd = serve_command()
+        .command("serve")
+        .help("answer JSON-RPC requests on a Unix socket")
+        .map(no_confirm);
+
+    let web_cmd = web_command()
+        .command("web")
+        .help("serve a web page and REST API for the project")
+        .map(no_confirm);
+
+    let merge_driver_cmd = merge_driver_command()
+        .command("merge-driver")
+        .help("merge two versions of a project file, used by git")
+        .map(no_confirm);
+
+    let git_cmd = git_command()
+        .command("git")
+        .help("git integration")
+        .map(no_confirm);
+
     // TODO: completions cmd compat
 
-    let cmd = construct!([new_cmd, add_cmd, done_cmd, rm_cmd, edit_cmd]).fallback(Command::Show);
+    let cmd = construct!([
+        show_cmd,
+        new_cmd,
+        add_cmd,
+        done_cmd,
+        rm_cmd,
+        edit_cmd,
+        estimate_cmd,
+        move_cmd,
+        sort_cmd,
+        rename_cmd,
+        export_cmd,
+        import_cmd,
+        scan_cmd,
+        archive_cmd,
+        search_cmd,
+        agenda_cmd,
+        start_cmd,
+        stop_cmd,
+        report_cmd,
+        stats_cmd,
+        project_cmd,
+        watch_cmd,
+        prompt_cmd,
+        serve_cmd,
+        web_cmd,
+        merge_driver_cmd,
+        git_cmd
+    ]);
+
+    let cmd = if external {
+        let external_cmd = external_command().map(no_confirm);
+        construct!([cmd, external_cmd]).boxed()
+    } else {
+        cmd.boxed()
+    }
+    .fallback(no_confirm(Command::Show(ShowOptions::default())));
+
+    let color = long("color")
+        .help("when to use colors: auto, always or never")
+        .argument::<String>("WHEN")
+        .parse(|v| v.parse::<ColorChoice>())
+        .optional();
+
+    let ascii = long("ascii")
+        .help("only use ascii characters for drawing")
+        .switch();
 
-    construct!(App { cmd })
+    construct!(color, ascii, cmd)
+        .map(|(color, ascii, (cmd, confirm))| App {
+            color,
+            ascii,
+            cmd,
+            confirm,
+        })
         .to_options()
         .version(concat!("tutel v", env!("CARGO_PKG_VERSION")))
         .descr("tutel\na minimalistic todo app for terminal enthusiasts")
-        .footer("run without a subcommand to show the todo list")
+        .footer("run without a subcommand to show the todo list\nother subcommands run tutel-<name> from PATH, `tutel help` lists them")
+}
+
+/// The built-in subcommands and their short forms, which aliases can't replace
+pub const COMMANDS: &[&str] = &[
+    "show",
+    "new",
+    "add",
+    "a",
+    "done",
+    "d",
+    "rm",
+    "edit",
+    "e",
+    "estimate",
+    "move",
+    "sort",
+    "rename",
+    "export",
+    "import",
+    "scan",
+    "archive",
+    "search",
+    "agenda",
+    "start",
+    "stop",
+    "report",
+    "stats",
+    "project",
+    "watch",
+    "prompt",
+    "serve",
+    "web",
+    "merge-driver",
+    "git",
+    "help",
+];
+
+/// Parses the command line after expanding `aliases`, exits on parse errors.
+/// Errors from expanding the aliases are left to the caller to report.
+pub fn parse_cli(aliases: &Aliases) -> Result<App, String> {
+    let (args, complete): (Vec<OsString>, Vec<OsString>) =
+        std::env::args_os().skip(1).partition(|a| {
+            a.to_str()
+                .is_none_or(|a| !a.starts_with("--bpaf-complete-"))
+        });
+
+    // aliases are expanded for completions too, to complete their arguments
+    let revision = match complete.as_slice() {
+        [] => None,
+        [flag] => match flag
+            .to_str()
+            .and_then(|f| f.strip_prefix("--bpaf-complete-rev="))
+            .and_then(|rev| rev.parse().ok())
+        {
+            Some(revision) => Some(revision),
+            None => return Ok(parser().run()),
+        },
+        // rendering the completion script is left to bpaf
+        _ => return Ok(parser().run()),
+    };
+
+    let args = aliases.expand(args, revision.is_some())?;
+    let run = |parser: OptionParser<App>| {
+        let mut parsed = Args::from(args.as_slice());
+        if let Some(revision) = revision {
+            parsed = parsed.set_comp(revision);
+        }
+
+        parser
+            .run_inner(parsed)
+            .unwrap_or_else(|e| std::process::exit(e.exit_code()))
+    };
+
+    let app = run(parser());
+
+    // reparse without them to get the usual error and suggestions
+    if let Command::External { name, .. } = &app.cmd {
+        if name != "help" && external::find(name).is_none() {
+            return Ok(run(parser_with(false)));
+        }
+    }
+
+    Ok(app)
+}
+
+/// The help of `tutel`, or of one of its subcommands
+pub fn help(command: Option<&str>) -> String {
+    let args: Vec<&str> = command.into_iter().chain(["--help"]).collect();
+
+    match parser_with(false).run_inner(Args::from(args.as_slice())) {
+        Err(ParseFailure::Stdout(help) | ParseFailure::Stderr(help)) => help,
+        Ok(_) => String::new(),
+    }
+}
+
+/// Adds `--dry-run` and `--yes` to a command that changes projects
+fn confirmed(cmd: impl Parser<Command>) -> impl Parser<(Command, Confirm)> {
+    let dry_run = short('n')
+        .long("dry-run")
+        .help("print what would change without saving it")
+        .switch();
+    let yes = short('y')
+        .long("yes")
+        .help("don't ask before removing or completing tasks in bulk")
+        .switch();
+    let confirm = construct!(Confirm { dry_run, yes });
+
+    // positionals have to come last
+    construct!(confirm, cmd).map(|(confirm, cmd)| (cmd, confirm))
+}
+
+/// For commands that don't change projects
+const fn no_confirm(cmd: Command) -> (Command, Confirm) {
+    (
+        cmd,
+        Confirm {
+            dry_run: false,
+            yes: false,
+        },
+    )
+}
+
+fn external_command() -> impl Parser<Command> {
+    let name = positional::<String>("COMMAND");
+    let args = any::<OsString>("ARGS").many();
+
+    construct!(Command::External { name, args }).hide()
 }
 
-pub fn parse_cli() -> App {
-    parser().run()
+fn show_command() -> OptionParser<Command> {
+    let ids = long("ids").help("show the id of every task").switch();
+    let budget = long("budget")
+        .help("only list the most important open tasks that fit, e.g. 4h or 10pts")
+        .argument::<Estimate>("ESTIMATE")
+        .optional();
+
+    construct!(ShowOptions { ids, budget })
+        .map(Command::Show)
+        .to_options()
+        .descr("show the todo list of the project and all of its children")
 }
 
-fn new_project_command() -> OptionParser<Command> {
+fn new_project_command() -> OptionParser<(Command, Confirm)> {
     let name = positional("name").optional();
     let force = short('f')
         .long("force")
         .help("force project creation")
         .switch();
 
-    construct!(Command::NewProject { force, name })
+    confirmed(construct!(Command::NewProject { force, name }))
         .to_options()
         .descr("create a new project in the current directory")
 }
 
-fn add_task_command() -> OptionParser<Command> {
+fn add_task_command() -> OptionParser<(Command, Confirm)> {
     let desc = positional::<String>("description")
         .many()
         .guard(|v| !v.is_empty(), "the task description is required")
@@ -97,12 +544,60 @@ fn add_task_command() -> OptionParser<Command> {
         .help("mark the task as already completed")
         .switch();
 
-    construct!(Command::AddTask { completed, desc })
+    let due = long("due")
+        .help("when the task is due: YYYY-MM-DD, today or tomorrow")
+        .argument::<String>("DATE")
+        .parse(|v| parse_date(&v))
+        .optional();
+
+    let tags = short('t')
+        .long("tag")
+        .help("tag the task, can be given multiple times")
+        .argument::<String>("TAG")
+        .many();
+
+    let priority = short('p')
+        .long("priority")
+        .help("how important the task is, 1 being the most important")
+        .argument::<u8>("N")
+        .optional();
+
+    let estimate = long("estimate")
+        .help("how much effort the task takes, e.g. 2h, 90m or 3pts")
+        .argument::<Estimate>("ESTIMATE")
+        .optional();
+
+    let single = construct!(Command::AddTask {
+        completed,
+        due,
+        tags,
+        priority,
+        estimate,
+        desc
+    });
+
+    // `tutel add -` or `tutel add --file FILE` add one task per line
+    let file = long("file")
+        .help("add one task per line of FILE")
+        .argument::<PathBuf>("FILE")
+        .map(Some);
+    let stdin = positional::<String>("-")
+        .help("add one task per line of stdin")
+        .guard(|v| v == "-", "expected -")
+        .map(|_| None);
+    let source = construct!([file, stdin]);
+    let metadata = short('m')
+        .long("metadata")
+        .help("read due:YYYY-MM-DD, #tag, p:N and ~ESTIMATE from every line")
+        .switch();
+    let bulk = construct!(Command::BulkAdd { metadata, source });
+
+    confirmed(construct!([bulk, single]))
         .to_options()
         .descr("add a new task. aliases: a")
 }
 
-fn task_completed_command() -> OptionParser<Command> {
+fn task_completed_command() -> OptionParser<(Command, Confirm)> {
     let completed = short('!')
         .long("not")
         .help("mark the task as not being done")
@@ -114,12 +609,12 @@ fn task_completed_command() -> OptionParser<Command> {
         .req_flag(TaskSelector::All);
 
     let selector = construct!([parse_indices(), all]);
-    construct!(Command::MarkCompletion(completed, selector))
+    confirmed(construct!(Command::MarkCompletion(completed, selector)))
         .to_options()
         .descr("mark a task as being done. aliases: d")
 }
 
-fn remove_task_command() -> OptionParser<Command> {
+fn remove_task_command() -> OptionParser<(Command, Confirm)> {
     let all = short('a')
         .long("all")
         .help("remove all tasks")
@@ -131,20 +626,26 @@ fn remove_task_command() -> OptionParser<Command> {
         .req_flag(TaskSelector::Completed);
 
     let project = long("project")
-        .help("remove the whole project file")
-        .req_flag(Command::RemoveProject);
+        .help("remove the project file of the nearest project")
+        .req_flag(());
+    let recursive = short('r')
+        .long("recursive")
+        .help("with --project, also remove the project files of the projects above it")
+        .switch();
+    let project =
+        construct!(project, recursive).map(|((), recursive)| Command::RemoveProject { recursive });
 
     let remove_task = construct!([parse_indices(), all, cleanup]).map(Command::RemoveTask);
 
-    construct!([remove_task, project])
+    confirmed(construct!([remove_task, project]))
         .to_options()
         .descr("remove a task from a project")
 }
 
-fn edit_task_command() -> OptionParser<Command> {
+fn edit_task_command() -> OptionParser<(Command, Confirm)> {
     let index = positional("index")
         .complete(complete_index)
-        .parse(|v: String| v.parse::<usize>());
+        .parse(|v: String| v.parse::<TaskRef>());
 
     let editor = env("EDITOR")
         .short('e')
@@ -152,11 +653,304 @@ fn edit_task_command() -> OptionParser<Command> {
         .help("the editor to use (default: $EDITOR)")
         .argument("editor");
 
-    construct!(Command::EditTask(editor, index))
+    confirmed(construct!(Command::EditTask(editor, index)))
         .to_options()
         .descr("edit an existing task. aliases: e")
 }
 
+fn estimate_command() -> OptionParser<(Command, Confirm)> {
+    let index = positional("index")
+        .help("task index, project:index or id prefix")
+        .complete(complete_index)
+        .parse(|v: String| v.parse::<TaskRef>());
+
+    let estimate = positional::<Estimate>("estimate")
+        .help("e.g. 2h, 90m or 3pts, clears the estimate if omitted")
+        .optional();
+
+    confirmed(construct!(Command::EstimateTask(index, estimate)))
+        .to_options()
+        .descr("set how much effort a task takes")
+}
+
+fn move_command() -> OptionParser<(Command, Confirm)> {
+    let index = positional("index")
+        .help("task index, project:index or id prefix")
+        .complete(complete_index)
+        .parse(|v: String| v.parse::<TaskRef>());
+
+    let before = long("before")
+        .help("move the task right before another one")
+        .argument::<String>("INDEX")
+        .parse(|v| v.parse::<TaskRef>())
+        .map(MoveTo::Before);
+    let top = long("top")
+        .help("move the task to the top")
+        .req_flag(MoveTo::Top);
+    let bottom = long("bottom")
+        .help("move the task to the bottom")
+        .req_flag(MoveTo::Bottom);
+    let to = construct!([before, top, bottom]);
+
+    // positionals have to come last
+    confirmed(construct!(to, index).map(|(to, index)| Command::MoveTask(index, to)))
+        .to_options()
+        .descr("change where a task is listed, its index stays the same")
+}
+
+fn sort_command() -> OptionParser<(Command, Confirm)> {
+    let by = long("by")
+        .help("priority, due, desc or index")
+        .argument::<SortKey>("KEY");
+
+    confirmed(construct!(Command::SortTasks(by)))
+        .to_options()
+        .descr("permanently reorder the tasks of the project")
+}
+
+fn archive_command() -> OptionParser<(Command, Confirm)> {
+    let list = pure(ArchiveAction::List)
+        .to_options()
+        .descr("list the archived tasks of the project and all of its children")
+        .command("list")
+        .help("list archived tasks");
+
+    let query = positional::<String>("query").help("text to search for");
+    let search = construct!(ArchiveAction::Search(query))
+        .to_options()
+        .descr("search the description, tags and project of archived tasks")
+        .command("search")
+        .help("search archived tasks");
+
+    let ids = positional::<String>("ids")
+        .help("ids or id prefixes of archived tasks")
+        .some("one or more ids are required");
+    let restore = construct!(ArchiveAction::Restore(ids))
+        .to_options()
+        .descr("move archived tasks back to the project they were archived from")
+        .command("restore")
+        .help("move archived tasks back");
+
+    let action = construct!([list, search, restore])
+        .fallback(ArchiveAction::Store)
+        .map(Command::Archive);
+
+    confirmed(action)
+        .to_options()
+        .descr("move completed tasks of the project and all of its children to the archive")
+}
+
+fn search_command() -> OptionParser<Command> {
+    let regex = short('r')
+        .long("regex")
+        .help("treat PATTERN as a regular expression")
+        .switch();
+    let ignore_case = short('i')
+        .long("ignore-case")
+        .help("ignore the case of letters")
+        .switch();
+    let all = short('a')
+        .long("all")
+        .help("also search the projects listed in the [search] section of the config")
+        .switch();
+    let pattern = positional::<String>("PATTERN").help("the text to search for");
+
+    construct!(Command::Search {
+        regex,
+        ignore_case,
+        all,
+        pattern
+    })
+    .to_options()
+    .descr("search the descriptions of the tasks of the project and all of its children. every match is listed as project:index")
+}
+
+fn agenda_command() -> OptionParser<Command> {
+    let days = long("days")
+        .help("only show tasks due within N days")
+        .argument::<u32>("N")
+        .optional();
+    let all = short('a')
+        .long("all")
+        .help("also include the projects listed in the [search] section of the config")
+        .switch();
+
+    construct!(Command::Agenda { days, all })
+        .to_options()
+        .descr("show the open tasks of the project and all of its children grouped by due date")
+}
+
+fn start_command() -> OptionParser<(Command, Confirm)> {
+    let index = positional("index")
+        .help("task index, project:index or id prefix")
+        .complete(complete_index)
+        .parse(|v: String| v.parse::<TaskRef>());
+
+    confirmed(construct!(Command::StartTimer(index)))
+        .to_options()
+        .descr("start a timer on a task, stopping the one that is running")
+}
+
+fn report_command() -> OptionParser<Command> {
+    let week = short('w')
+        .long("week")
+        .help("only count time tracked this week")
+        .switch();
+    let csv = long("csv").help("print the report as CSV").switch();
+
+    construct!(Command::Report { week, csv })
+        .to_options()
+        .descr("show the time tracked on the tasks of the project and all of its children")
+}
+
+fn stats_command() -> OptionParser<Command> {
+    let json = long("json").help("print the statistics as JSON").switch();
+
+    construct!(Command::Stats { json })
+        .to_options()
+        .descr("show task counts, weekly activity and a burndown chart of the project tree")
+}
+
+fn watch_command() -> OptionParser<Command> {
+    let poll = long("poll")
+        .help("check the project files periodically instead of using inotify")
+        .switch();
+
+    construct!(Command::Watch { poll }).to_options().descr(
+        "show the project tree and redraw it whenever one of its project files changes, including newly created children",
+    )
+}
+
+fn prompt_command() -> OptionParser<Command> {
+    let format = long("format")
+        .help("placeholders: {name}, {open}, {done}, {total}, {overdue} and {today}. text in parentheses is left out when all of its numbers are 0 (default: {done}/{total}( ⚑{overdue}))")
+        .argument::<String>("FORMAT")
+        .optional();
+    let timeout = long("timeout")
+        .help("give up and print nothing after this many milliseconds (default: 100)")
+        .argument::<u64>("MS")
+        .fallback(100);
+
+    construct!(Command::Prompt { format, timeout }).to_options().descr(
+        "print task counts of the nearest project for PS1, starship or powerline. prints nothing outside of projects",
+    )
+}
+
+fn serve_command() -> OptionParser<Command> {
+    let socket = long("socket")
+        .help("the Unix socket to listen on")
+        .argument::<PathBuf>("PATH");
+
+    construct!(Command::Serve { socket })
+        .to_options()
+        .descr("keep the project tree loaded and answer JSON-RPC requests (list, add, complete, edit, remove, subscribe), one per line")
+}
+
+fn web_command() -> OptionParser<Command> {
+    let listen = long("listen")
+        .help("the loopback address to listen on (default: 127.0.0.1:8080)")
+        .argument::<String>("ADDR")
+        .fallback(String::from("127.0.0.1:8080"));
+
+    construct!(Command::Web { listen }).to_options().descr(
+        "serve a web page and a REST API to view and check off the tasks of the project tree",
+    )
+}
+
+fn merge_driver_command() -> OptionParser<Command> {
+    let base = positional::<PathBuf>("BASE").help("the common ancestor (%O)");
+    let ours = positional::<PathBuf>("OURS").help("our version, receives the result (%A)");
+    let theirs = positional::<PathBuf>("THEIRS").help("their version (%B)");
+
+    construct!(Command::MergeDriver { base, ours, theirs })
+        .to_options()
+        .descr("three-way merge of project files, see 'tutel git install-merge-driver'")
+}
+
+fn rename_command() -> OptionParser<(Command, Confirm)> {
+    let name = positional::<String>("NAME")
+        .help("the new name")
+        .guard(|n| !n.trim().is_empty(), "the name must not be empty");
+
+    confirmed(construct!(Command::RenameProject(name)))
+        .to_options()
+        .descr("rename the project in the current directory, or the nearest one above it")
+}
+
+fn project_command() -> OptionParser<Command> {
+    let info = pure(Command::ProjectInfo)
+        .to_options()
+        .descr("show the path, task counts, parent and children of the project in the current directory, or the nearest one above it")
+        .command("info")
+        .help("show details about the nearest project");
+
+    construct!([info])
+        .to_options()
+        .descr("details about the nearest project")
+}
+
+fn git_command() -> OptionParser<Command> {
+    let install = pure(Command::InstallMergeDriver)
+        .to_options()
+        .descr("register tutel as merge driver for project files in the current repository")
+        .command("install-merge-driver")
+        .help("register tutel as merge driver for project files");
+
+    construct!([install]).to_options().descr("git integration")
+}
+
+/// Parses a date given on the command line
+fn parse_date(input: &str) -> Result<NaiveDate, String> {
+    let today = Local::now().date_naive();
+
+    match input {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today + Duration::days(1)),
+        _ => NaiveDate::parse_from_str(input, "%Y-%m-%d")
+            .map_err(|_| format!("not a valid date: {input}, expected YYYY-MM-DD")),
+    }
+}
+
+fn format() -> impl Parser<Format> {
+    long("format")
+        .help("the format to use: markdown (default) or ics")
+        .argument::<String>("FORMAT")
+        .parse(|v| v.parse::<Format>())
+        .fallback(Format::Markdown)
+}
+
+fn export_command() -> OptionParser<Command> {
+    let format = format();
+    let indices = short('i')
+        .long("indices")
+        .help("include task indices")
+        .switch();
+
+    construct!(Command::Export { format, indices })
+        .to_options()
+        .descr("print the project and all of its children in another format")
+}
+
+fn import_command() -> OptionParser<(Command, Confirm)> {
+    let format = format();
+    let headings = long("headings")
+        .help("add tasks below a heading to the child project with that name")
+        .switch();
+    let file = positional::<PathBuf>("file")
+        .help("the file to import, reads stdin if omitted or '-'")
+        .optional()
+        .map(|file| file.filter(|f| f.as_os_str() != "-"));
+
+    confirmed(construct!(Command::Import {
+        format,
+        headings,
+        file
+    }))
+    .to_options()
+    .descr("add the tasks from a file to the project")
+}
+
+#[allow(clippy::ptr_arg)] // bpaf hands us the whole Vec
 fn complete_indices(input: &Vec<String>) -> Vec<(String, Option<String>)> {
     let workdir = match std::env::current_dir() {
         Ok(x) => x,
@@ -212,16 +1006,14 @@ fn complete_index(input: &String) -> Vec<(String, Option<String>)> {
 
 fn parse_indices() -> impl Parser<TaskSelector> {
     positional("indices")
+        .help("task indices, project:index or id prefixes")
         .some("one or more task indices are required")
         .complete(complete_indices)
         .parse::<_, _, String>(|v| {
             let mut indices = Vec::with_capacity(v.len());
 
             for x in v {
-                indices.push(
-                    x.parse::<usize>()
-                        .map_err(|_| format!("not a valid index: {x}"))?,
-                )
+                indices.push(x.parse::<TaskRef>()?);
             }
 
             Ok(TaskSelector::Indexed(indices))
@@ -230,14 +1022,49 @@ fn parse_indices() -> impl Parser<TaskSelector> {
 
 #[cfg(test)]
 mod tests {
-    use super::{parser, Command, TaskSelector};
+    use super::{
+        parser, ArchiveAction, Command, Confirm, Format, MoveTo, ShowOptions, TaskSelector,
+    };
+    use crate::theme::ColorChoice;
     use bpaf::Args;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+    use tutel::{estimate::Estimate, SortKey, TaskRef};
 
     #[test]
     fn no_args() {
         let app = parser().run_inner(Args::from(&[])).unwrap();
 
-        assert_eq!(app.cmd, Command::Show);
+        assert_eq!(app.cmd, Command::Show(ShowOptions::default()));
+    }
+
+    #[test]
+    fn show() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["show", "--ids"]))
+                .unwrap()
+                .cmd,
+            Command::Show(ShowOptions {
+                ids: true,
+                budget: None
+            })
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["show", "--budget", "4h"]))
+                .unwrap()
+                .cmd,
+            Command::Show(ShowOptions {
+                ids: false,
+                budget: Some(Estimate::Minutes(240))
+            })
+        );
+        assert!(parser
+            .run_inner(Args::from(&["show", "--budget", "soon"]))
+            .is_err());
     }
 
     #[test]
@@ -283,7 +1110,11 @@ mod tests {
                 .cmd,
             Command::AddTask {
                 desc: String::from("test not what"),
-                completed: false
+                completed: false,
+                due: None,
+                tags: Vec::new(),
+                priority: None,
+                estimate: None,
             },
         );
 
@@ -294,73 +1125,668 @@ mod tests {
                 .cmd,
             Command::AddTask {
                 desc: String::from("test"),
-                completed: true
+                completed: true,
+                due: None,
+                tags: Vec::new(),
+                priority: None,
+                estimate: None,
             },
         );
-    }
-
-    #[test]
-    fn task_completed() {
-        let parser = parser();
 
         assert_eq!(
             parser
-                .run_inner(Args::from(&["done", "--all", "-!"]))
+                .run_inner(Args::from(&[
+                    "add",
+                    "--due",
+                    "2026-10-20",
+                    "-t",
+                    "work",
+                    "--tag",
+                    "urgent",
+                    "test"
+                ]))
                 .unwrap()
                 .cmd,
-            Command::MarkCompletion(false, TaskSelector::All)
+            Command::AddTask {
+                desc: String::from("test"),
+                completed: false,
+                due: NaiveDate::from_ymd_opt(2026, 10, 20),
+                tags: vec![String::from("work"), String::from("urgent")],
+                priority: None,
+                estimate: None,
+            },
         );
+
+        assert!(parser
+            .run_inner(Args::from(&["add", "--due", "someday", "test"]))
+            .is_err());
+
         assert_eq!(
             parser
-                .run_inner(Args::from(&["done", "4", "2", "42"]))
+                .run_inner(Args::from(&[
+                    "add",
+                    "-p",
+                    "1",
+                    "--estimate",
+                    "3pts",
+                    "test"
+                ]))
                 .unwrap()
                 .cmd,
-            Command::MarkCompletion(true, TaskSelector::Indexed(vec![4, 2, 42]))
+            Command::AddTask {
+                desc: String::from("test"),
+                completed: false,
+                due: None,
+                tags: Vec::new(),
+                priority: Some(1),
+                estimate: Some(Estimate::Points(3)),
+            },
         );
     }
 
     #[test]
-    fn remove_task() {
+    fn estimate_task() {
         let parser = parser();
 
         assert_eq!(
-            parser.run_inner(Args::from(&["rm", "-c"])).unwrap().cmd,
-            Command::RemoveTask(TaskSelector::Completed)
+            parser
+                .run_inner(Args::from(&["estimate", "4", "1h30m"]))
+                .unwrap()
+                .cmd,
+            Command::EstimateTask(4.into(), Some(Estimate::Minutes(90)))
         );
-
         assert_eq!(
             parser
-                .run_inner(Args::from(&["rm", "4", "2", "42"]))
+                .run_inner(Args::from(&["estimate", "4"]))
                 .unwrap()
                 .cmd,
-            Command::RemoveTask(TaskSelector::Indexed(vec![4, 2, 42]))
+            Command::EstimateTask(4.into(), None)
         );
     }
 
     #[test]
-    fn edit_task() {
+    fn task_completed() {
         let parser = parser();
 
         assert_eq!(
             parser
-                .run_inner(Args::from(&["edit", "42", "--editor", "nvim"]))
+                .run_inner(Args::from(&["done", "--all", "-!"]))
+                .unwrap()
+                .cmd,
+            Command::MarkCompletion(false, TaskSelector::All)
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["done", "4", "2", "42"]))
                 .unwrap()
                 .cmd,
-            Command::EditTask(String::from("nvim"), 42)
+            Command::MarkCompletion(
+                true,
+                TaskSelector::Indexed(vec![4.into(), 2.into(), 42.into()])
+            )
         );
-    }
-
-    #[test]
-    fn remove_project() {
-        let parser = parser();
-
         assert_eq!(
             parser
-                .run_inner(Args::from(&["rm", "--project"]))
+                .run_inner(Args::from(&["done", "4", "3fa8"]))
                 .unwrap()
                 .cmd,
-            Command::RemoveProject
+            Command::MarkCompletion(
+                true,
+                TaskSelector::Indexed(vec![4.into(), TaskRef::Id(String::from("3fa8"))])
+            )
         );
+        assert!(parser
+            .run_inner(Args::from(&["done", "sub:000", "sub:"]))
+            .is_err());
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["done", "sub:000"]))
+                .unwrap()
+                .cmd,
+            Command::MarkCompletion(
+                true,
+                TaskSelector::Indexed(vec![TaskRef::InProject(String::from("sub"), 0)])
+            )
+        );
+    }
+
+    #[test]
+    fn move_task() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["move", "4", "--before", "2"]))
+                .unwrap()
+                .cmd,
+            Command::MoveTask(4.into(), MoveTo::Before(2.into()))
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["move", "--top", "3fa8"]))
+                .unwrap()
+                .cmd,
+            Command::MoveTask(TaskRef::Id(String::from("3fa8")), MoveTo::Top)
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["move", "4", "--bottom"]))
+                .unwrap()
+                .cmd,
+            Command::MoveTask(4.into(), MoveTo::Bottom)
+        );
+        assert!(parser.run_inner(Args::from(&["move", "4"])).is_err());
+        assert!(parser
+            .run_inner(Args::from(&["move", "4", "--top", "--bottom"]))
+            .is_err());
+    }
+
+    #[test]
+    fn sort_tasks() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["sort", "--by", "due"]))
+                .unwrap()
+                .cmd,
+            Command::SortTasks(SortKey::Due)
+        );
+        assert!(parser
+            .run_inner(Args::from(&["sort", "--by", "size"]))
+            .is_err());
+    }
+
+    #[test]
+    fn rename() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["rename", "backend"]))
+                .unwrap()
+                .cmd,
+            Command::RenameProject(String::from("backend"))
+        );
+        assert!(parser.run_inner(Args::from(&["rename", " "])).is_err());
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["project", "info"]))
+                .unwrap()
+                .cmd,
+            Command::ProjectInfo
+        );
+    }
+
+    #[test]
+    fn watch() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["watch"])).unwrap().cmd,
+            Command::Watch { poll: false }
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["watch", "--poll"]))
+                .unwrap()
+                .cmd,
+            Command::Watch { poll: true }
+        );
+    }
+
+    #[test]
+    fn prompt() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["prompt"])).unwrap().cmd,
+            Command::Prompt {
+                format: None,
+                timeout: 100
+            }
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&[
+                    "prompt",
+                    "--format",
+                    "{open} open",
+                    "--timeout",
+                    "20"
+                ]))
+                .unwrap()
+                .cmd,
+            Command::Prompt {
+                format: Some(String::from("{open} open")),
+                timeout: 20
+            }
+        );
+    }
+
+    #[test]
+    fn external() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["hello", "world", "--loud", "-x"]))
+                .unwrap()
+                .cmd,
+            Command::External {
+                name: String::from("hello"),
+                args: vec!["world".into(), "--loud".into(), "-x".into()]
+            }
+        );
+        assert_eq!(
+            parser.run_inner(Args::from(&["help"])).unwrap().cmd,
+            Command::External {
+                name: String::from("help"),
+                args: Vec::new()
+            }
+        );
+        // built-in commands win
+        assert_eq!(
+            parser.run_inner(Args::from(&["stop"])).unwrap().cmd,
+            Command::StopTimer
+        );
+
+        assert!(super::help(None).contains("tutel help"));
+        assert!(super::help(Some("add")).contains("add a new task"));
+    }
+
+    #[test]
+    fn commands() {
+        // every subcommand listed in the help, with its short form
+        let help = super::help(None);
+        let listed: Vec<_> = help
+            .lines()
+            .skip_while(|l| !l.starts_with("Available commands"))
+            .skip(1)
+            .take_while(|l| !l.is_empty())
+            .flat_map(|l| l.trim().split("  ").next().unwrap().split(", "))
+            .collect();
+
+        // `help` comes last, it is handled like an external subcommand
+        assert_eq!(listed, super::COMMANDS[..super::COMMANDS.len() - 1]);
+    }
+
+    #[test]
+    fn serve() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["serve", "--socket", "/tmp/tutel.sock"]))
+                .unwrap()
+                .cmd,
+            Command::Serve {
+                socket: PathBuf::from("/tmp/tutel.sock")
+            }
+        );
+        assert!(parser.run_inner(Args::from(&["serve"])).is_err());
+    }
+
+    #[test]
+    fn web() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["web"])).unwrap().cmd,
+            Command::Web {
+                listen: String::from("127.0.0.1:8080")
+            }
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["web", "--listen", "127.0.0.1:9000"]))
+                .unwrap()
+                .cmd,
+            Command::Web {
+                listen: String::from("127.0.0.1:9000")
+            }
+        );
+    }
+
+    #[test]
+    fn remove_task() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["rm", "-c"])).unwrap().cmd,
+            Command::RemoveTask(TaskSelector::Completed)
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["rm", "4", "2", "42"]))
+                .unwrap()
+                .cmd,
+            Command::RemoveTask(TaskSelector::Indexed(vec![4.into(), 2.into(), 42.into()]))
+        );
+    }
+
+    #[test]
+    fn edit_task() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["edit", "42", "--editor", "nvim"]))
+                .unwrap()
+                .cmd,
+            Command::EditTask(String::from("nvim"), 42.into())
+        );
+    }
+
+    #[test]
+    fn remove_project() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["rm", "--project"]))
+                .unwrap()
+                .cmd,
+            Command::RemoveProject { recursive: false }
+        );
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["rm", "--project", "-r"]))
+                .unwrap()
+                .cmd,
+            Command::RemoveProject { recursive: true }
+        );
+    }
+
+    #[test]
+    fn confirm() {
+        let parser = parser();
+
+        let app = parser
+            .run_inner(Args::from(&["rm", "--all", "--dry-run"]))
+            .unwrap();
+        assert_eq!(app.cmd, Command::RemoveTask(TaskSelector::All));
+        assert_eq!(
+            app.confirm,
+            Confirm {
+                dry_run: true,
+                yes: false
+            }
+        );
+
+        let app = parser.run_inner(Args::from(&["done", "-y", "3"])).unwrap();
+        assert_eq!(
+            app.cmd,
+            Command::MarkCompletion(true, TaskSelector::Indexed(vec![3.into()]))
+        );
+        assert!(app.confirm.yes);
+
+        // only for commands that change projects
+        assert!(parser
+            .run_inner(Args::from(&["show", "--dry-run"]))
+            .is_err());
+    }
+
+    #[test]
+    fn export() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["export"])).unwrap().cmd,
+            Command::Export {
+                format: Format::Markdown,
+                indices: false
+            }
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["export", "--format", "md", "-i"]))
+                .unwrap()
+                .cmd,
+            Command::Export {
+                format: Format::Markdown,
+                indices: true
+            }
+        );
+    }
+
+    #[test]
+    fn import() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["import", "--headings", "README.md"]))
+                .unwrap()
+                .cmd,
+            Command::Import {
+                format: Format::Markdown,
+                file: Some(PathBuf::from("README.md")),
+                headings: true
+            }
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["import", "--format", "ics", "todo.ics"]))
+                .unwrap()
+                .cmd,
+            Command::Import {
+                format: Format::Ics,
+                file: Some(PathBuf::from("todo.ics")),
+                headings: false
+            }
+        );
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["import", "-"])).unwrap().cmd,
+            Command::Import {
+                format: Format::Markdown,
+                file: None,
+                headings: false
+            }
+        );
+    }
+
+    #[test]
+    fn scan() {
+        assert_eq!(
+            parser().run_inner(Args::from(&["scan"])).unwrap().cmd,
+            Command::Scan
+        );
+    }
+
+    #[test]
+    fn archive() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["archive"])).unwrap().cmd,
+            Command::Archive(ArchiveAction::Store)
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["archive", "list"]))
+                .unwrap()
+                .cmd,
+            Command::Archive(ArchiveAction::List)
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["archive", "search", "release"]))
+                .unwrap()
+                .cmd,
+            Command::Archive(ArchiveAction::Search(String::from("release")))
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["archive", "restore", "3fa8", "b1"]))
+                .unwrap()
+                .cmd,
+            Command::Archive(ArchiveAction::Restore(vec![
+                String::from("3fa8"),
+                String::from("b1")
+            ]))
+        );
+
+        assert!(parser
+            .run_inner(Args::from(&["archive", "restore"]))
+            .is_err());
+    }
+
+    #[test]
+    fn search() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["search", "-r", "-i", "fix.*docs"]))
+                .unwrap()
+                .cmd,
+            Command::Search {
+                pattern: String::from("fix.*docs"),
+                regex: true,
+                ignore_case: true,
+                all: false
+            }
+        );
+
+        assert!(parser.run_inner(Args::from(&["search"])).is_err());
+    }
+
+    #[test]
+    fn agenda() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["agenda"])).unwrap().cmd,
+            Command::Agenda {
+                days: None,
+                all: false
+            }
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["agenda", "--days", "7", "-a"]))
+                .unwrap()
+                .cmd,
+            Command::Agenda {
+                days: Some(7),
+                all: true
+            }
+        );
+
+        assert!(parser
+            .run_inner(Args::from(&["agenda", "--days", "-1"]))
+            .is_err());
+    }
+
+    #[test]
+    fn timer() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["start", "3"])).unwrap().cmd,
+            Command::StartTimer(3.into())
+        );
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["stop"])).unwrap().cmd,
+            Command::StopTimer
+        );
+
+        let app = parser
+            .run_inner(Args::from(&["start", "3", "--dry-run"]))
+            .unwrap();
+        assert_eq!(app.cmd, Command::StartTimer(3.into()));
+        assert!(app.confirm.dry_run);
+        assert!(
+            parser
+                .run_inner(Args::from(&["stop", "-n"]))
+                .unwrap()
+                .confirm
+                .dry_run
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["report", "--week", "--csv"]))
+                .unwrap()
+                .cmd,
+            Command::Report {
+                week: true,
+                csv: true
+            }
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let parser = parser();
+
+        assert_eq!(
+            parser.run_inner(Args::from(&["stats"])).unwrap().cmd,
+            Command::Stats { json: false }
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["stats", "--json"]))
+                .unwrap()
+                .cmd,
+            Command::Stats { json: true }
+        );
+    }
+
+    #[test]
+    fn merge_driver() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["merge-driver", "base", "ours", "theirs"]))
+                .unwrap()
+                .cmd,
+            Command::MergeDriver {
+                base: PathBuf::from("base"),
+                ours: PathBuf::from("ours"),
+                theirs: PathBuf::from("theirs")
+            }
+        );
+
+        assert_eq!(
+            parser
+                .run_inner(Args::from(&["git", "install-merge-driver"]))
+                .unwrap()
+                .cmd,
+            Command::InstallMergeDriver
+        );
+    }
+
+    #[test]
+    fn color() {
+        let parser = parser();
+
+        let app = parser.run_inner(Args::from(&["--color", "never"])).unwrap();
+        assert_eq!(app.color, Some(ColorChoice::Never));
+        assert!(!app.ascii);
+
+        let app = parser
+            .run_inner(Args::from(&["--ascii", "add", "test"]))
+            .unwrap();
+        assert_eq!(app.color, None);
+        assert!(app.ascii);
+
+        assert!(parser
+            .run_inner(Args::from(&["--color", "sometimes"]))
+            .is_err());
     }
 
     #[test]
@@ -0,0 +1,211 @@
+//! An append-only archive of completed tasks, kept outside of the project
+//! files so they don't grow forever.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    error::{Error, Result},
+    project::now,
+    Project, Task,
+};
+
+/// The key of the array of tables every entry is stored in
+const ENTRIES_KEY: &str = "archived";
+
+/// A task that was moved to the archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedTask {
+    /// Path of the project file the task was archived from
+    pub project: PathBuf,
+    pub project_name: String,
+    pub archived_at: DateTime<Utc>,
+    pub task: Task,
+}
+
+impl ArchivedTask {
+    /// Whether `query` appears in the description, tags or project name,
+    /// ignoring case
+    #[must_use]
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        self.task.desc.to_lowercase().contains(&query)
+            || self.project_name.to_lowercase().contains(&query)
+            || self.task.tags.iter().any(|t| t.to_lowercase() == query)
+    }
+}
+
+/// A TOML file archived tasks are appended to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    path: PathBuf,
+}
+
+impl Archive {
+    #[must_use]
+    pub const fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Reads every entry of the archive, oldest first. A missing archive is
+    /// empty.
+    ///
+    /// # Errors
+    /// This function will return an error if the archive exists but could not
+    /// be read or parsed.
+    pub fn load(&self) -> Result<Vec<ArchivedTask>> {
+        if !self.path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path).map_err(|e| Error::io(&self.path, e))?;
+        let mut file: BTreeMap<String, Vec<ArchivedTask>> =
+            toml::from_str(&content).map_err(|e| Error::parse(&self.path, &e))?;
+
+        Ok(file.remove(ENTRIES_KEY).unwrap_or_default())
+    }
+
+    /// Appends `entries` to the archive, creating it if necessary
+    ///
+    /// # Errors
+    /// This function will return an error if the archive could not be
+    /// written.
+    pub fn append(&self, entries: &[ArchivedTask]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+
+        let serialized = serialize(entries)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| Error::io(&self.path, e))?;
+        writeln!(file, "{serialized}").map_err(|e| Error::io(&self.path, e))?;
+
+        Ok(())
+    }
+
+    /// Removes every entry for which `select` returns true from the archive
+    /// and returns them.
+    ///
+    /// # Errors
+    /// This function will return an error if the archive could not be read or
+    /// rewritten.
+    pub fn take(&self, select: impl Fn(&ArchivedTask) -> bool) -> Result<Vec<ArchivedTask>> {
+        let (taken, kept): (Vec<_>, Vec<_>) = self.load()?.into_iter().partition(|e| select(e));
+
+        if !taken.is_empty() {
+            let serialized = serialize(&kept)?;
+            fs::write(&self.path, serialized).map_err(|e| Error::io(&self.path, e))?;
+        }
+
+        Ok(taken)
+    }
+}
+
+fn serialize(entries: &[ArchivedTask]) -> Result<String> {
+    let file = BTreeMap::from([(ENTRIES_KEY, entries)]);
+    Ok(toml::to_string_pretty(&file)?)
+}
+
+/// Removes all completed tasks from `project`, but not from its children,
+/// and returns them as archive entries. The project still has to be saved.
+pub fn take_completed(project: &mut Project) -> Vec<ArchivedTask> {
+    let archived_at = now();
+    let (completed, open) = std::mem::take(&mut project.data.tasks)
+        .into_iter()
+        .partition(|t: &Task| t.completed);
+    project.data.tasks = open;
+
+    completed
+        .into_iter()
+        .map(|task| ArchivedTask {
+            project: project.path().to_path_buf(),
+            project_name: project.data.name.clone(),
+            archived_at,
+            task,
+        })
+        .collect()
+}
+
+/// Adds an archived task back to `project`. It keeps its index unless that
+/// is taken by now.
+///
+/// # Errors
+/// This function will return an error if all indices of `project` are in use.
+pub fn restore(project: &mut Project, entry: ArchivedTask) -> Result<()> {
+    let mut task = entry.task;
+    if project.data.tasks.iter().any(|t| t.index == task.index) {
+        task.index = project.next_index()?;
+    }
+
+    project.data.tasks.push(task);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{restore, take_completed, Archive};
+    use crate::Project;
+
+    #[test]
+    fn archive_and_restore() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let archive = Archive::new(tmpdir.path().join("data/archive.toml"));
+        assert!(archive.load().unwrap().is_empty());
+
+        let mut project = Project::new(PathBuf::from("/p/.tutel.toml"), 0, "p".to_string(), false);
+        project.add(String::from("open"), false).unwrap();
+        project.add(String::from("first"), true).unwrap().tags = vec![String::from("work")];
+        project.add(String::from("second"), true).unwrap();
+
+        let entries = take_completed(&mut project);
+        assert_eq!(project.data.tasks.len(), 1);
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].task.completed_at.is_some());
+
+        // two appends end up in the same file
+        archive.append(&entries[..1]).unwrap();
+        archive.append(&entries[1..]).unwrap();
+        assert_eq!(archive.load().unwrap(), entries);
+
+        assert!(entries[0].matches("WORK"));
+        assert!(entries[1].matches("sec"));
+        assert!(!entries[1].matches("work"));
+
+        let id = entries[1].task.id.clone();
+        let taken = archive.take(|e| e.task.id == id).unwrap();
+        assert_eq!(taken, entries[1..]);
+        assert_eq!(archive.load().unwrap(), entries[..1]);
+
+        // index 2 was taken in the meantime
+        project.add(String::from("another"), false).unwrap();
+        project.add(String::from("more"), false).unwrap();
+        for entry in taken {
+            restore(&mut project, entry).unwrap();
+        }
+        let indices: Vec<_> = project.data.tasks.iter().map(|t| t.index).collect();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+        assert_eq!(project.data.tasks[3].id, id);
+    }
+}
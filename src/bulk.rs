@@ -0,0 +1,203 @@
+//! Adding many tasks at once from plain text, one task per line.
+
+use chrono::NaiveDate;
+
+use crate::{estimate::Estimate, Project, Result};
+
+/// A task read from a line of text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub desc: String,
+    pub completed: bool,
+    pub due: Option<NaiveDate>,
+    pub tags: Vec<String>,
+    pub priority: Option<u8>,
+    pub estimate: Option<Estimate>,
+}
+
+/// Reads one task per line, empty lines are skipped. A leading `[x]` or
+/// `x ` marks a task as completed, `[ ]` and list markers (`- `, `* `) are
+/// dropped.
+///
+/// With `metadata`, the words `due:YYYY-MM-DD`, `#tag`, `p:N` and
+/// `~ESTIMATE` (e.g. `~2h`) are taken out of the description and set on the
+/// task. Words that look like metadata but don't parse are left alone.
+#[must_use]
+pub fn parse(input: &str, metadata: bool) -> Vec<Item> {
+    input
+        .lines()
+        .filter_map(|line| parse_line(line, metadata))
+        .collect()
+}
+
+fn parse_line(line: &str, metadata: bool) -> Option<Item> {
+    let mut line = line.trim();
+    for marker in ["- ", "* "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            line = rest.trim_start();
+            break;
+        }
+    }
+
+    let mut completed = false;
+    for (prefix, done) in [("[x]", true), ("[X]", true), ("[ ]", false), ("x ", true)] {
+        if let Some(rest) = line.strip_prefix(prefix) {
+            line = rest.trim_start();
+            completed = done;
+            break;
+        }
+    }
+
+    let mut item = Item {
+        desc: String::new(),
+        completed,
+        due: None,
+        tags: Vec::new(),
+        priority: None,
+        estimate: None,
+    };
+
+    let mut words = Vec::new();
+    for word in line.split_whitespace() {
+        if !(metadata && parse_metadata(word, &mut item)) {
+            words.push(word);
+        }
+    }
+    item.desc = words.join(" ");
+
+    (!item.desc.is_empty()).then_some(item)
+}
+
+/// Sets the metadata `word` stands for on `item`, returns whether it did
+fn parse_metadata(word: &str, item: &mut Item) -> bool {
+    if let Some(due) = word.strip_prefix("due:") {
+        if let Ok(due) = NaiveDate::parse_from_str(due, "%Y-%m-%d") {
+            item.due = Some(due);
+            return true;
+        }
+    } else if let Some(tag) = word.strip_prefix('#') {
+        if !tag.is_empty() {
+            item.tags.push(tag.to_string());
+            return true;
+        }
+    } else if let Some(priority) = word.strip_prefix("p:") {
+        if let Ok(priority) = priority.parse() {
+            item.priority = Some(priority);
+            return true;
+        }
+    } else if let Some(estimate) = word.strip_prefix('~') {
+        if let Ok(estimate) = estimate.parse() {
+            item.estimate = Some(estimate);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Adds every item to `project` and returns the indices they were given.
+/// Nothing is saved.
+///
+/// # Errors
+/// This function will return an error if `project` runs out of indices.
+pub fn add_all(project: &mut Project, items: Vec<Item>) -> Result<Vec<usize>> {
+    items
+        .into_iter()
+        .map(|item| {
+            let task = project.add(item.desc, item.completed)?;
+            task.due = item.due;
+            task.tags = item.tags;
+            task.priority = item.priority;
+            task.estimate = item.estimate;
+            Ok(task.index)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{add_all, parse, Item};
+    use crate::{estimate::Estimate, Error, Project, Task};
+
+    fn item(desc: &str, completed: bool) -> Item {
+        Item {
+            desc: String::from(desc),
+            completed,
+            due: None,
+            tags: Vec::new(),
+            priority: None,
+            estimate: None,
+        }
+    }
+
+    #[test]
+    fn parse_lines() {
+        let input =
+            "write docs\n\n  [x] done already\nx also done\n- [ ] from a list\n* #not a tag\n[x]\n";
+
+        assert_eq!(
+            parse(input, false),
+            vec![
+                item("write docs", false),
+                item("done already", true),
+                item("also done", true),
+                item("from a list", false),
+                item("#not a tag", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let items = parse(
+            "call bob due:2026-10-20 #work p:1 ~30m\nfix #42 due:someday ~soon",
+            true,
+        );
+
+        assert_eq!(
+            items,
+            vec![
+                Item {
+                    due: NaiveDate::from_ymd_opt(2026, 10, 20),
+                    tags: vec![String::from("work")],
+                    priority: Some(1),
+                    estimate: Some(Estimate::Minutes(30)),
+                    ..item("call bob", false)
+                },
+                Item {
+                    tags: vec![String::from("42")],
+                    ..item("fix due:someday ~soon", false)
+                },
+            ]
+        );
+
+        let mut project = Project::new(PathBuf::new(), 0, String::from("p"), false);
+        project.add(String::from("existing"), false).unwrap();
+        assert_eq!(add_all(&mut project, items).unwrap(), vec![1, 2]);
+        assert_eq!(project.data.tasks[1].priority, Some(1));
+    }
+
+    #[test]
+    fn add_across_wraparound() {
+        let mut project = Project::new(PathBuf::new(), 0, String::from("p"), false);
+        for index in [0, 1, 3, 997] {
+            project.data.tasks.push(Task::new("existing", false, index));
+        }
+
+        let items = vec![item("a", false), item("b", false), item("c", false)];
+        assert_eq!(add_all(&mut project, items).unwrap(), vec![998, 999, 2]);
+
+        let items = (0..993).map(|i| item(&i.to_string(), false)).collect();
+        assert_eq!(add_all(&mut project, items).unwrap().len(), 993);
+        assert_eq!(project.data.tasks.len(), 1000);
+
+        assert!(matches!(
+            add_all(&mut project, vec![item("one too many", false)]),
+            Err(Error::ProjectFull(_))
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use toml::value::Table;
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The user configuration, read from `$XDG_CONFIG_HOME/tutel/config.toml`
+/// (or `~/.config/tutel/config.toml`). Every section is optional.
+#[derive(Debug, Default)]
+pub struct Config {
+    table: Table,
+}
+
+impl Config {
+    /// Loads the user configuration. A missing config file is not an error,
+    /// an empty configuration is returned instead.
+    pub fn load() -> Result<Self> {
+        let Some(path) = config_path() else {
+            return Ok(Self::default());
+        };
+
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).context("unable to read config file")?;
+        let table = toml::from_str(&content)
+            .with_context(|| format!("invalid config file: {}", path.to_string_lossy()))?;
+
+        Ok(Self { table })
+    }
+
+    /// Returns the table with the given name, if present
+    pub fn section(&self, name: &str) -> Option<&Table> {
+        self.table.get(name).and_then(toml::Value::as_table)
+    }
+}
+
+/// Determines the directory the user configuration lives in
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir).join("tutel"));
+    }
+
+    env::var_os("HOME")
+        .filter(|v| !v.is_empty())
+        .map(|home| PathBuf::from(home).join(".config").join("tutel"))
+}
+
+/// Determines the directory data like the archive is kept in,
+/// `$XDG_DATA_HOME/tutel` (or `~/.local/share/tutel`)
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir).join("tutel"));
+    }
+
+    env::var_os("HOME").filter(|v| !v.is_empty()).map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("tutel")
+    })
+}
+
+/// Determines the directory state like the running timer is kept in,
+/// `$XDG_STATE_HOME/tutel` (or `~/.local/state/tutel`)
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_STATE_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir).join("tutel"));
+    }
+
+    env::var_os("HOME").filter(|v| !v.is_empty()).map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("state")
+            .join("tutel")
+    })
+}
+
+fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE_NAME))
+}
@@ -1,25 +1,36 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{
     de::{self, Visitor},
     Deserialize,
 };
 
-use super::{project::ProjectData, Task};
+use super::{
+    archive::ArchivedTask,
+    estimate::Estimate,
+    hooks::{Hooks, Settings},
+    project::ProjectData,
+    scan::CodeMarker,
+    timer::{ActiveTimer, Interval},
+    Task,
+};
 
-const PROJECT_DATA_FIELDS: &[&str] = &["name", "tasks", "is_child"];
+const PROJECT_DATA_FIELDS: &[&str] = &["name", "tasks", "is_child", "code", "hooks"];
 
 enum ProjectDataField {
     Name,
     Tasks,
     IsChild,
+    Code,
+    Hooks,
 }
 
 struct ProjectDataFieldVisitor;
 
-impl<'de> Visitor<'de> for ProjectDataFieldVisitor {
+impl Visitor<'_> for ProjectDataFieldVisitor {
     type Value = ProjectDataField;
 
     fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        fmt.write_str("'name' or 'tasks' or 'is_child")
+        fmt.write_str("'name', 'tasks', 'is_child', 'code' or 'hooks'")
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -30,6 +41,8 @@ impl<'de> Visitor<'de> for ProjectDataFieldVisitor {
             "name" => Ok(ProjectDataField::Name),
             "tasks" => Ok(ProjectDataField::Tasks),
             "is_child" => Ok(ProjectDataField::IsChild),
+            "code" => Ok(ProjectDataField::Code),
+            "hooks" => Ok(ProjectDataField::Hooks),
             _ => Err(de::Error::unknown_field(v, PROJECT_DATA_FIELDS)),
         }
     }
@@ -60,6 +73,8 @@ impl<'de> Visitor<'de> for ProjectDataVisitor {
         let mut name = None;
         let mut tasks = None;
         let mut is_child = None;
+        let mut code = None;
+        let mut hooks = None;
         while let Some(key) = map.next_key()? {
             match key {
                 ProjectDataField::Name => {
@@ -80,17 +95,33 @@ impl<'de> Visitor<'de> for ProjectDataVisitor {
                     }
                     is_child = Some(map.next_value()?);
                 }
+                ProjectDataField::Code => {
+                    if code.is_some() {
+                        return Err(de::Error::duplicate_field("code"));
+                    }
+                    code = Some(map.next_value()?);
+                }
+                ProjectDataField::Hooks => {
+                    if hooks.is_some() {
+                        return Err(de::Error::duplicate_field("hooks"));
+                    }
+                    hooks = Some(map.next_value()?);
+                }
             }
         }
 
         let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
         let tasks = tasks.ok_or_else(|| de::Error::missing_field("tasks"))?;
         let is_child = is_child.unwrap_or(false);
+        let code = code.unwrap_or_default();
+        let hooks = hooks.unwrap_or_default();
 
         Ok(ProjectData {
             name,
             tasks,
             is_child,
+            code,
+            hooks,
         })
     }
 }
@@ -104,21 +135,43 @@ impl<'de> Deserialize<'de> for ProjectData {
     }
 }
 
-const TASK_FIELDS: &[&str] = &["name", "index", "completed"];
+const TASK_FIELDS: &[&str] = &[
+    "name",
+    "index",
+    "completed",
+    "created_at",
+    "completed_at",
+    "due",
+    "tags",
+    "priority",
+    "estimate",
+    "time",
+    "id",
+];
 
 enum TaskField {
     Name,
     Index,
     Completed,
+    CreatedAt,
+    CompletedAt,
+    Due,
+    Tags,
+    Priority,
+    Estimate,
+    Time,
+    Id,
 }
 
 struct TaskFieldVisitor;
 
-impl<'de> Visitor<'de> for TaskFieldVisitor {
+impl Visitor<'_> for TaskFieldVisitor {
     type Value = TaskField;
 
     fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        fmt.write_str("'name', 'index' or 'completed'")
+        fmt.write_str(
+            "'name', 'index', 'completed', 'created_at', 'completed_at', 'due', 'tags', 'priority', 'estimate', 'time' or 'id'",
+        )
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -129,6 +182,14 @@ impl<'de> Visitor<'de> for TaskFieldVisitor {
             "name" | "desc" => Ok(TaskField::Name),
             "index" => Ok(TaskField::Index),
             "completed" => Ok(TaskField::Completed),
+            "created_at" => Ok(TaskField::CreatedAt),
+            "completed_at" => Ok(TaskField::CompletedAt),
+            "due" => Ok(TaskField::Due),
+            "tags" => Ok(TaskField::Tags),
+            "priority" => Ok(TaskField::Priority),
+            "estimate" => Ok(TaskField::Estimate),
+            "time" => Ok(TaskField::Time),
+            "id" => Ok(TaskField::Id),
             _ => Err(de::Error::unknown_field(v, TASK_FIELDS)),
         }
     }
@@ -152,6 +213,8 @@ impl<'de> Visitor<'de> for TaskVisitor {
         fmt.write_str("struct Task")
     }
 
+    // one arm per field
+    #[allow(clippy::too_many_lines)]
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: de::MapAccess<'de>,
@@ -159,6 +222,14 @@ impl<'de> Visitor<'de> for TaskVisitor {
         let mut description = None;
         let mut index = None;
         let mut completed = None;
+        let mut created_at = None;
+        let mut completed_at = None;
+        let mut due = None;
+        let mut tags = None;
+        let mut priority = None;
+        let mut estimate = None;
+        let mut time = None;
+        let mut id = None;
         while let Some(key) = map.next_key()? {
             match key {
                 TaskField::Name => {
@@ -179,6 +250,66 @@ impl<'de> Visitor<'de> for TaskVisitor {
                     }
                     completed = Some(map.next_value()?);
                 }
+                TaskField::CreatedAt => {
+                    if created_at.is_some() {
+                        return Err(de::Error::duplicate_field("created_at"));
+                    }
+                    let value: String = map.next_value()?;
+                    let value = DateTime::parse_from_rfc3339(&value).map_err(de::Error::custom)?;
+                    created_at = Some(value.with_timezone(&Utc));
+                }
+                TaskField::CompletedAt => {
+                    if completed_at.is_some() {
+                        return Err(de::Error::duplicate_field("completed_at"));
+                    }
+                    let value: String = map.next_value()?;
+                    let value = DateTime::parse_from_rfc3339(&value).map_err(de::Error::custom)?;
+                    completed_at = Some(value.with_timezone(&Utc));
+                }
+                TaskField::Due => {
+                    if due.is_some() {
+                        return Err(de::Error::duplicate_field("due"));
+                    }
+                    let value: String = map.next_value()?;
+                    due = Some(value.parse::<NaiveDate>().map_err(de::Error::custom)?);
+                }
+                TaskField::Tags => {
+                    if tags.is_some() {
+                        return Err(de::Error::duplicate_field("tags"));
+                    }
+                    tags = Some(map.next_value()?);
+                }
+                TaskField::Priority => {
+                    if priority.is_some() {
+                        return Err(de::Error::duplicate_field("priority"));
+                    }
+                    priority = Some(map.next_value()?);
+                }
+                TaskField::Estimate => {
+                    if estimate.is_some() {
+                        return Err(de::Error::duplicate_field("estimate"));
+                    }
+                    let value: String = map.next_value()?;
+                    estimate = Some(value.parse::<Estimate>().map_err(de::Error::custom)?);
+                }
+                TaskField::Time => {
+                    if time.is_some() {
+                        return Err(de::Error::duplicate_field("time"));
+                    }
+                    let value: Vec<String> = map.next_value()?;
+                    time = Some(
+                        value
+                            .iter()
+                            .map(|v| v.parse::<Interval>().map_err(de::Error::custom))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                TaskField::Id => {
+                    if id.is_some() {
+                        return Err(de::Error::duplicate_field("id"));
+                    }
+                    id = Some(map.next_value()?);
+                }
             }
         }
 
@@ -190,6 +321,15 @@ impl<'de> Visitor<'de> for TaskVisitor {
             desc,
             index,
             completed,
+            created_at,
+            completed_at,
+            due,
+            tags: tags.unwrap_or_default(),
+            priority,
+            estimate,
+            time: time.unwrap_or_default(),
+            // filled in by Project::load
+            id: id.unwrap_or_default(),
         })
     }
 }
@@ -202,3 +342,491 @@ impl<'de> Deserialize<'de> for Task {
         deserializer.deserialize_struct("Task", TASK_FIELDS, TaskVisitor)
     }
 }
+
+const CODE_MARKER_FIELDS: &[&str] = &["kind", "text", "file", "line"];
+
+enum CodeMarkerField {
+    Kind,
+    Text,
+    File,
+    Line,
+}
+
+struct CodeMarkerFieldVisitor;
+
+impl Visitor<'_> for CodeMarkerFieldVisitor {
+    type Value = CodeMarkerField;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("'kind', 'text', 'file' or 'line'")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match v {
+            "kind" => Ok(CodeMarkerField::Kind),
+            "text" => Ok(CodeMarkerField::Text),
+            "file" => Ok(CodeMarkerField::File),
+            "line" => Ok(CodeMarkerField::Line),
+            _ => Err(de::Error::unknown_field(v, CODE_MARKER_FIELDS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CodeMarkerField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(CodeMarkerFieldVisitor)
+    }
+}
+
+struct CodeMarkerVisitor;
+
+impl<'de> Visitor<'de> for CodeMarkerVisitor {
+    type Value = CodeMarker;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("struct CodeMarker")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut kind = None;
+        let mut text = None;
+        let mut file = None;
+        let mut line = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                CodeMarkerField::Kind => {
+                    if kind.is_some() {
+                        return Err(de::Error::duplicate_field("kind"));
+                    }
+                    kind = Some(map.next_value()?);
+                }
+                CodeMarkerField::Text => {
+                    if text.is_some() {
+                        return Err(de::Error::duplicate_field("text"));
+                    }
+                    text = Some(map.next_value()?);
+                }
+                CodeMarkerField::File => {
+                    if file.is_some() {
+                        return Err(de::Error::duplicate_field("file"));
+                    }
+                    file = Some(map.next_value()?);
+                }
+                CodeMarkerField::Line => {
+                    if line.is_some() {
+                        return Err(de::Error::duplicate_field("line"));
+                    }
+                    line = Some(map.next_value()?);
+                }
+            }
+        }
+
+        let kind = kind.ok_or_else(|| de::Error::missing_field("kind"))?;
+        let text = text.unwrap_or_default();
+        let file = file.ok_or_else(|| de::Error::missing_field("file"))?;
+        let line = line.ok_or_else(|| de::Error::missing_field("line"))?;
+
+        Ok(CodeMarker {
+            kind,
+            text,
+            file,
+            line,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for CodeMarker {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("CodeMarker", CODE_MARKER_FIELDS, CodeMarkerVisitor)
+    }
+}
+
+const HOOKS_FIELDS: &[&str] = &["pre-save", "on-add", "on-complete", "on-remove", "on-edit"];
+const SETTINGS_FIELDS: &[&str] = &[
+    "pre-save",
+    "on-add",
+    "on-complete",
+    "on-remove",
+    "on-edit",
+    "project_hooks",
+];
+
+enum HooksField {
+    PreSave,
+    OnAdd,
+    OnComplete,
+    OnRemove,
+    OnEdit,
+    ProjectHooks,
+}
+
+struct HooksFieldVisitor;
+
+impl Visitor<'_> for HooksFieldVisitor {
+    type Value = HooksField;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("'pre-save', 'on-add', 'on-complete', 'on-remove' or 'on-edit'")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match v {
+            "pre-save" => Ok(HooksField::PreSave),
+            "on-add" => Ok(HooksField::OnAdd),
+            "on-complete" => Ok(HooksField::OnComplete),
+            "on-remove" => Ok(HooksField::OnRemove),
+            "on-edit" => Ok(HooksField::OnEdit),
+            "project_hooks" => Ok(HooksField::ProjectHooks),
+            _ => Err(de::Error::unknown_field(v, SETTINGS_FIELDS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HooksField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(HooksFieldVisitor)
+    }
+}
+
+/// The commands of a hook, either a single one or a list
+struct Commands(Vec<String>);
+
+struct CommandsVisitor;
+
+impl<'de> Visitor<'de> for CommandsVisitor {
+    type Value = Commands;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("a command or a list of commands")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Commands(vec![v.to_owned()]))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut commands = Vec::new();
+        while let Some(command) = seq.next_element()? {
+            commands.push(command);
+        }
+
+        Ok(Commands(commands))
+    }
+}
+
+impl<'de> Deserialize<'de> for Commands {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(CommandsVisitor)
+    }
+}
+
+/// Reads [`Settings`], or only [`Hooks`] when `project_hooks` is not allowed
+struct HooksVisitor {
+    settings: bool,
+}
+
+impl<'de> Visitor<'de> for HooksVisitor {
+    type Value = Settings;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("a table of hooks")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut hooks = Hooks::default();
+        let mut project_hooks = None;
+        while let Some(key) = map.next_key()? {
+            let (name, commands) = match key {
+                HooksField::PreSave => ("pre-save", &mut hooks.pre_save),
+                HooksField::OnAdd => ("on-add", &mut hooks.on_add),
+                HooksField::OnComplete => ("on-complete", &mut hooks.on_complete),
+                HooksField::OnRemove => ("on-remove", &mut hooks.on_remove),
+                HooksField::OnEdit => ("on-edit", &mut hooks.on_edit),
+                HooksField::ProjectHooks => {
+                    if !self.settings {
+                        return Err(de::Error::unknown_field("project_hooks", HOOKS_FIELDS));
+                    }
+                    if project_hooks.is_some() {
+                        return Err(de::Error::duplicate_field("project_hooks"));
+                    }
+                    project_hooks = Some(map.next_value()?);
+                    continue;
+                }
+            };
+
+            if !commands.is_empty() {
+                return Err(de::Error::duplicate_field(name));
+            }
+            *commands = map.next_value::<Commands>()?.0;
+        }
+
+        Ok(Settings {
+            global: hooks,
+            project_hooks: project_hooks.unwrap_or(false),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Hooks {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let settings = HooksVisitor { settings: false };
+        let settings = deserializer.deserialize_struct("Hooks", HOOKS_FIELDS, settings)?;
+        Ok(settings.global)
+    }
+}
+
+impl<'de> Deserialize<'de> for Settings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let settings = HooksVisitor { settings: true };
+        deserializer.deserialize_struct("Settings", SETTINGS_FIELDS, settings)
+    }
+}
+
+const ARCHIVED_TASK_FIELDS: &[&str] = &["project", "project_name", "archived_at", "task"];
+
+enum ArchivedTaskField {
+    Project,
+    ProjectName,
+    ArchivedAt,
+    Task,
+}
+
+struct ArchivedTaskFieldVisitor;
+
+impl Visitor<'_> for ArchivedTaskFieldVisitor {
+    type Value = ArchivedTaskField;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("'project', 'project_name', 'archived_at' or 'task'")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match v {
+            "project" => Ok(ArchivedTaskField::Project),
+            "project_name" => Ok(ArchivedTaskField::ProjectName),
+            "archived_at" => Ok(ArchivedTaskField::ArchivedAt),
+            "task" => Ok(ArchivedTaskField::Task),
+            _ => Err(de::Error::unknown_field(v, ARCHIVED_TASK_FIELDS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ArchivedTaskField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(ArchivedTaskFieldVisitor)
+    }
+}
+
+struct ArchivedTaskVisitor;
+
+impl<'de> Visitor<'de> for ArchivedTaskVisitor {
+    type Value = ArchivedTask;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("struct ArchivedTask")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut project = None;
+        let mut project_name = None;
+        let mut archived_at = None;
+        let mut task = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                ArchivedTaskField::Project => {
+                    if project.is_some() {
+                        return Err(de::Error::duplicate_field("project"));
+                    }
+                    project = Some(map.next_value::<String>()?.into());
+                }
+                ArchivedTaskField::ProjectName => {
+                    if project_name.is_some() {
+                        return Err(de::Error::duplicate_field("project_name"));
+                    }
+                    project_name = Some(map.next_value()?);
+                }
+                ArchivedTaskField::ArchivedAt => {
+                    if archived_at.is_some() {
+                        return Err(de::Error::duplicate_field("archived_at"));
+                    }
+                    let value: String = map.next_value()?;
+                    let value = DateTime::parse_from_rfc3339(&value).map_err(de::Error::custom)?;
+                    archived_at = Some(value.with_timezone(&Utc));
+                }
+                ArchivedTaskField::Task => {
+                    if task.is_some() {
+                        return Err(de::Error::duplicate_field("task"));
+                    }
+                    task = Some(map.next_value()?);
+                }
+            }
+        }
+
+        let project = project.ok_or_else(|| de::Error::missing_field("project"))?;
+        let project_name = project_name.unwrap_or_default();
+        let archived_at = archived_at.ok_or_else(|| de::Error::missing_field("archived_at"))?;
+        let task = task.ok_or_else(|| de::Error::missing_field("task"))?;
+
+        Ok(ArchivedTask {
+            project,
+            project_name,
+            archived_at,
+            task,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for ArchivedTask {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("ArchivedTask", ARCHIVED_TASK_FIELDS, ArchivedTaskVisitor)
+    }
+}
+
+const ACTIVE_TIMER_FIELDS: &[&str] = &["project", "task", "started_at"];
+
+enum ActiveTimerField {
+    Project,
+    Task,
+    StartedAt,
+}
+
+struct ActiveTimerFieldVisitor;
+
+impl Visitor<'_> for ActiveTimerFieldVisitor {
+    type Value = ActiveTimerField;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("'project', 'task' or 'started_at'")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match v {
+            "project" => Ok(ActiveTimerField::Project),
+            "task" => Ok(ActiveTimerField::Task),
+            "started_at" => Ok(ActiveTimerField::StartedAt),
+            _ => Err(de::Error::unknown_field(v, ACTIVE_TIMER_FIELDS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ActiveTimerField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(ActiveTimerFieldVisitor)
+    }
+}
+
+struct ActiveTimerVisitor;
+
+impl<'de> Visitor<'de> for ActiveTimerVisitor {
+    type Value = ActiveTimer;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("struct ActiveTimer")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut project = None;
+        let mut task_id = None;
+        let mut started_at = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                ActiveTimerField::Project => {
+                    if project.is_some() {
+                        return Err(de::Error::duplicate_field("project"));
+                    }
+                    project = Some(map.next_value::<String>()?.into());
+                }
+                ActiveTimerField::Task => {
+                    if task_id.is_some() {
+                        return Err(de::Error::duplicate_field("task"));
+                    }
+                    task_id = Some(map.next_value()?);
+                }
+                ActiveTimerField::StartedAt => {
+                    if started_at.is_some() {
+                        return Err(de::Error::duplicate_field("started_at"));
+                    }
+                    let value: String = map.next_value()?;
+                    let value = DateTime::parse_from_rfc3339(&value).map_err(de::Error::custom)?;
+                    started_at = Some(value.with_timezone(&Utc));
+                }
+            }
+        }
+
+        let project = project.ok_or_else(|| de::Error::missing_field("project"))?;
+        let task_id = task_id.ok_or_else(|| de::Error::missing_field("task"))?;
+        let started_at = started_at.ok_or_else(|| de::Error::missing_field("started_at"))?;
+
+        Ok(ActiveTimer {
+            project,
+            task_id,
+            started_at,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for ActiveTimer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("ActiveTimer", ACTIVE_TIMER_FIELDS, ActiveTimerVisitor)
+    }
+}
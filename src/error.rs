@@ -0,0 +1,147 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Everything that can go wrong when working with projects
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// No project could be found in a directory or any of its parents
+    NoProject(PathBuf),
+    /// The file does not exist or is not a regular file
+    NotAProject(PathBuf),
+    /// No task matched a selector
+    TaskNotFound { project: String, task: String },
+    /// An id prefix matched more than one task
+    AmbiguousTask {
+        prefix: String,
+        candidates: Vec<String>,
+    },
+    /// No project in the tree is named like a selector
+    ProjectNotFound(String),
+    /// A selector matched more than one project
+    AmbiguousProject {
+        selector: String,
+        candidates: Vec<String>,
+    },
+    /// A project file contains invalid TOML or is missing required fields.
+    /// `line` and `column` start at 1.
+    Parse {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// A project could not be serialized
+    Serialize(toml::ser::Error),
+    /// Two tasks within the same project have the same index
+    DuplicateIndex { path: PathBuf, index: usize },
+    /// Every index of a project is in use, no task can be added
+    ProjectFull(String),
+    /// The project file was changed by someone else since it was loaded
+    LockConflict(PathBuf),
+    /// Reading or writing a file failed
+    Io { path: PathBuf, source: io::Error },
+    /// A hook could not be run
+    Hook { command: String, source: io::Error },
+    /// A `pre-save` hook rejected a change, nothing was saved
+    HookRejected { command: String, task: String },
+}
+
+/// A `Result` with [`enum@Error`] as error type
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn parse(path: impl Into<PathBuf>, error: &toml::de::Error) -> Self {
+        let (line, column) = error
+            .line_col()
+            .map_or((None, None), |(l, c)| (Some(l + 1), Some(c + 1)));
+
+        Self::Parse {
+            path: path.into(),
+            line,
+            column,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoProject(path) => {
+                write!(f, "no project found in {}", path.to_string_lossy())
+            }
+            Self::NotAProject(path) => {
+                write!(f, "not a project file: {}", path.to_string_lossy())
+            }
+            Self::TaskNotFound { project, task } => write!(f, "no task {project}:{task} found"),
+            Self::AmbiguousTask { prefix, candidates } => write!(
+                f,
+                "id prefix {} is ambiguous, candidates are: {}",
+                prefix,
+                candidates.join(", ")
+            ),
+            Self::ProjectNotFound(selector) => write!(f, "no project named {selector} found"),
+            Self::AmbiguousProject {
+                selector,
+                candidates,
+            } => write!(
+                f,
+                "project name {} is ambiguous, candidates are: {}",
+                selector,
+                candidates.join(", ")
+            ),
+            Self::Parse { path, message, .. } => write!(
+                f,
+                "invalid project file syntax in {}: {}",
+                path.to_string_lossy(),
+                message
+            ),
+            Self::Serialize(_) => f.write_str("unable to serialize project"),
+            Self::DuplicateIndex { path, index } => write!(
+                f,
+                "index {} is used more than once in {}",
+                index,
+                path.to_string_lossy()
+            ),
+            Self::ProjectFull(name) => write!(
+                f,
+                "all {} indices of {} are in use, remove some tasks first",
+                crate::project::MAX_INDEX + 1,
+                name
+            ),
+            Self::LockConflict(path) => write!(
+                f,
+                "{} was changed by someone else, reload and try again",
+                path.to_string_lossy()
+            ),
+            Self::Io { path, .. } => write!(f, "unable to access {}", path.to_string_lossy()),
+            Self::Hook { command, .. } => write!(f, "unable to run hook `{command}`"),
+            Self::HookRejected { command, task } => {
+                write!(f, "hook `{command}` rejected the change to task {task}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serialize(e) => Some(e),
+            Self::Io { source, .. } | Self::Hook { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::Serialize(e)
+    }
+}
@@ -0,0 +1,294 @@
+//! Effort estimates of tasks and how much work is left.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use crate::{agenda::Entry, Project, Task};
+
+/// Minutes in a day of work, used for estimates given in days
+const MINUTES_PER_DAY: u32 = 8 * 60;
+
+/// How much effort a task takes, either as time or as story points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Estimate {
+    Minutes(u32),
+    Points(u32),
+}
+
+/// Formats as `90m`, `2h`, `1h30m` or `3pts`
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Points(points) => write!(f, "{points}pts"),
+            Self::Minutes(minutes) => {
+                let (hours, minutes) = (minutes / 60, minutes % 60);
+                match (hours, minutes) {
+                    (0, m) => write!(f, "{m}m"),
+                    (h, 0) => write!(f, "{h}h"),
+                    (h, m) => write!(f, "{h}h{m}m"),
+                }
+            }
+        }
+    }
+}
+
+/// Parses `3pts`/`3pt`/`3p`, or a time like `90m`, `1.5h`, `1h30m` or `2d`.
+/// A day has 8 hours.
+impl FromStr for Estimate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim().to_lowercase();
+        let invalid = || format!("not a valid estimate: {s}, expected e.g. 2h, 90m or 3pts");
+
+        for suffix in ["pts", "pt", "p"] {
+            if let Some(points) = input.strip_suffix(suffix) {
+                return points
+                    .trim()
+                    .parse()
+                    .map(Self::Points)
+                    .map_err(|_| invalid());
+            }
+        }
+
+        let mut minutes = 0.0;
+        let mut number = String::new();
+        for c in input.chars() {
+            let factor = match c {
+                '0'..='9' | '.' => {
+                    number.push(c);
+                    continue;
+                }
+                ' ' => continue,
+                'm' => 1.0,
+                'h' => 60.0,
+                'd' => f64::from(MINUTES_PER_DAY),
+                _ => return Err(invalid()),
+            };
+
+            let value: f64 = number.parse().map_err(|_| invalid())?;
+            minutes += value * factor;
+            number.clear();
+        }
+
+        if !number.is_empty() || input.is_empty() {
+            return Err(invalid());
+        }
+
+        if minutes.round() > f64::from(u32::MAX) {
+            return Err(format!("estimate too large: {s}"));
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(Self::Minutes(minutes.round() as u32))
+    }
+}
+
+/// Estimated effort, kept apart by unit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Effort {
+    pub minutes: u32,
+    pub points: u32,
+}
+
+impl Effort {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.minutes == 0 && self.points == 0
+    }
+
+    const fn add(self, estimate: Estimate) -> Self {
+        match estimate {
+            Estimate::Minutes(minutes) => Self {
+                minutes: self.minutes.saturating_add(minutes),
+                ..self
+            },
+            Estimate::Points(points) => Self {
+                points: self.points.saturating_add(points),
+                ..self
+            },
+        }
+    }
+
+    const fn merge(self, other: Self) -> Self {
+        Self {
+            minutes: self.minutes.saturating_add(other.minutes),
+            points: self.points.saturating_add(other.points),
+        }
+    }
+}
+
+/// The estimated effort of all tasks and of the open ones
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rollup {
+    pub total: Effort,
+    pub remaining: Effort,
+}
+
+/// Sums up the estimates of `project` and all of its children
+#[must_use]
+pub fn rollup(project: &Project) -> Rollup {
+    let mut rollup = Rollup::default();
+
+    for task in &project.data.tasks {
+        if let Some(estimate) = task.estimate {
+            rollup.total = rollup.total.add(estimate);
+            if !task.completed {
+                rollup.remaining = rollup.remaining.add(estimate);
+            }
+        }
+    }
+
+    for child in project.get_children() {
+        let child = self::rollup(child);
+        rollup.total = rollup.total.merge(child.total);
+        rollup.remaining = rollup.remaining.merge(child.remaining);
+    }
+
+    rollup
+}
+
+/// Picks open tasks of `project` and all of its children whose estimates
+/// fit into `budget`, most important first. Only tasks estimated in the same
+/// unit as the budget are considered.
+///
+/// Tasks are ordered by priority, then due date, then their order in the
+/// project. A task that does not fit is skipped, smaller ones after it may
+/// still be picked.
+#[must_use]
+pub fn budget(project: &Project, budget: Estimate) -> Vec<Entry<'_>> {
+    let mut candidates = Vec::new();
+    collect_open(project, &mut candidates);
+    candidates.sort_by(|a, b| by_importance(a.task, b.task));
+
+    let mut left = match budget {
+        Estimate::Minutes(v) | Estimate::Points(v) => v,
+    };
+    let mut picked = Vec::new();
+
+    for entry in candidates {
+        let ((Estimate::Minutes(_), Some(Estimate::Minutes(cost)))
+        | (Estimate::Points(_), Some(Estimate::Points(cost)))) = (budget, entry.task.estimate)
+        else {
+            continue;
+        };
+
+        if cost <= left {
+            left -= cost;
+            picked.push(entry);
+        }
+    }
+
+    picked
+}
+
+fn collect_open<'a>(project: &'a Project, entries: &mut Vec<Entry<'a>>) {
+    entries.extend(
+        project
+            .data
+            .tasks
+            .iter()
+            .filter(|t| !t.completed)
+            .map(|task| Entry {
+                project: &project.data.name,
+                task,
+            }),
+    );
+
+    for child in project.get_children() {
+        collect_open(child, entries);
+    }
+}
+
+/// Tasks with a priority come first, lower numbers are more important. Ties
+/// are broken by due date, tasks without one come last.
+#[must_use]
+pub fn by_importance(a: &Task, b: &Task) -> Ordering {
+    let priority = |t: &Task| t.priority.unwrap_or(u8::MAX);
+    let due = |t: &Task| (t.due.is_none(), t.due);
+
+    priority(a)
+        .cmp(&priority(b))
+        .then_with(|| due(a).cmp(&due(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{budget, rollup, Effort, Estimate};
+    use crate::Project;
+
+    #[test]
+    fn parse_and_display() {
+        let parse = |s: &str| s.parse::<Estimate>();
+
+        assert_eq!(parse("90m"), Ok(Estimate::Minutes(90)));
+        assert_eq!(parse("1.5h"), Ok(Estimate::Minutes(90)));
+        assert_eq!(parse("1h30m"), Ok(Estimate::Minutes(90)));
+        assert_eq!(parse("2d"), Ok(Estimate::Minutes(960)));
+        assert_eq!(parse("3pts"), Ok(Estimate::Points(3)));
+        assert_eq!(parse("5 p"), Ok(Estimate::Points(5)));
+        assert!(parse("2").is_err());
+        assert!(parse("h").is_err());
+        assert!(parse("2 weeks").is_err());
+        assert!(parse("").is_err());
+        assert!(parse("99999999999h").is_err());
+        assert!(parse("99999999999pts").is_err());
+        assert_eq!(parse("71582788h"), Ok(Estimate::Minutes(4_294_967_280)));
+
+        assert_eq!(Estimate::Minutes(90).to_string(), "1h30m");
+        assert_eq!(Estimate::Minutes(120).to_string(), "2h");
+        assert_eq!(Estimate::Minutes(45).to_string(), "45m");
+        assert_eq!(Estimate::Points(3).to_string(), "3pts");
+    }
+
+    #[test]
+    fn rollup_and_budget() {
+        let mut project = Project::new(PathBuf::new(), 0, "root".to_string(), false);
+        project.add(String::from("big"), false).unwrap().estimate = Some(Estimate::Minutes(180));
+        project.add(String::from("done"), true).unwrap().estimate = Some(Estimate::Minutes(60));
+        project.add(String::from("points"), false).unwrap().estimate = Some(Estimate::Points(3));
+        project.add(String::from("unestimated"), false).unwrap();
+
+        let mut child = Project::new(PathBuf::new(), 1, "child".to_string(), true);
+        let urgent = child.add(String::from("urgent"), false).unwrap();
+        urgent.estimate = Some(Estimate::Minutes(120));
+        urgent.priority = Some(1);
+        child.add(String::from("small"), false).unwrap().estimate = Some(Estimate::Minutes(30));
+        project.attach_child(child);
+
+        let rollup = rollup(&project);
+        assert_eq!(
+            rollup.total,
+            Effort {
+                minutes: 390,
+                points: 3
+            }
+        );
+        assert_eq!(
+            rollup.remaining,
+            Effort {
+                minutes: 330,
+                points: 3
+            }
+        );
+
+        let mut huge = Project::new(PathBuf::new(), 0, "huge".to_string(), false);
+        for _ in 0..2 {
+            huge.add(String::from("huge"), false).unwrap().estimate =
+                Some(Estimate::Minutes(u32::MAX));
+        }
+        assert_eq!(super::rollup(&huge).total.minutes, u32::MAX);
+
+        // urgent comes first, big does not fit anymore, small does
+        let picked: Vec<_> = budget(&project, Estimate::Minutes(240))
+            .iter()
+            .map(|e| e.task.desc.as_str())
+            .collect();
+        assert_eq!(picked, vec!["urgent", "small"]);
+
+        let picked = budget(&project, Estimate::Points(2));
+        assert!(picked.is_empty());
+    }
+}
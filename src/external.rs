@@ -0,0 +1,119 @@
+//! Subcommands provided by `tutel-<name>` executables on `PATH`, the way git
+//! does it.
+
+use std::{
+    collections::BTreeSet,
+    env::{self, consts::EXE_SUFFIX},
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{Context, Result};
+use tutel::Project;
+
+const PREFIX: &str = "tutel-";
+
+/// Finds the executable for the subcommand `name`
+pub fn find(name: &str) -> Option<PathBuf> {
+    // `tutel ../../bin/sh` must not leave the directories on PATH
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+
+    let file_name = format!("{PREFIX}{name}{EXE_SUFFIX}");
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+/// Names of all subcommands found on `PATH`, sorted
+pub fn discover() -> Vec<String> {
+    let Some(path) = env::var_os("PATH") else {
+        return Vec::new();
+    };
+
+    let mut names = BTreeSet::new();
+    for dir in env::split_paths(&path) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(std::result::Result::ok) {
+            let file_name = entry.file_name();
+            let name = file_name
+                .to_str()
+                .and_then(|n| n.strip_prefix(PREFIX))
+                .and_then(|n| n.strip_suffix(EXE_SUFFIX));
+
+            if let Some(name) = name.filter(|n| !n.is_empty()) {
+                if is_executable(&entry.path()) {
+                    names.insert(name.to_owned());
+                }
+            }
+        }
+    }
+
+    names.into_iter().collect()
+}
+
+/// Runs `executable` with `args` and returns its exit code. The project tree,
+/// if there is one, is passed on in environment variables:
+///
+/// - `TUTEL`: this executable, to call back into tutel
+/// - `TUTEL_ROOT`: the directory of the root project
+/// - `TUTEL_PROJECT`: the name of the root project
+/// - `TUTEL_PROJECT_FILE`: the project file of the root project
+/// - `TUTEL_PROJECT_FILES`: the project files of the whole tree, separated
+///   like `PATH`
+pub fn run(executable: &Path, args: &[OsString], project: Option<&Project>) -> Result<i32> {
+    let mut command = process::Command::new(executable);
+    command.args(args);
+
+    if let Ok(tutel) = env::current_exe() {
+        command.env("TUTEL", tutel);
+    }
+
+    if let Some(p) = project {
+        let mut files = Vec::new();
+        collect_files(p, &mut files);
+
+        if let Some(root) = p.path().parent() {
+            command.env("TUTEL_ROOT", root);
+        }
+        command
+            .env("TUTEL_PROJECT", &p.data.name)
+            .env("TUTEL_PROJECT_FILE", p.path())
+            .env(
+                "TUTEL_PROJECT_FILES",
+                env::join_paths(files).context("project path contains a path separator")?,
+            );
+    }
+
+    let status = command
+        .status()
+        .with_context(|| format!("unable to run {}", executable.to_string_lossy()))?;
+
+    // killed by a signal otherwise
+    Ok(status.code().unwrap_or(1))
+}
+
+fn collect_files<'a>(p: &'a Project, files: &mut Vec<&'a Path>) {
+    files.push(p.path());
+    for child in p.get_children() {
+        collect_files(child, files);
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,217 @@
+//! User scripts that run when tasks change.
+//!
+//! Hooks are shell commands, configured per project in the `[hooks]` table of
+//! the project file or for every project in the user configuration. They run
+//! in the directory of the project, once for every changed task, which they
+//! get as JSON on stdin. `TUTEL_EVENT`, `TUTEL_CHANGE`, `TUTEL_PROJECT` and
+//! `TUTEL_PROJECT_FILE` tell them what happened where.
+//!
+//! `pre-save` hooks run before a project is written and can reject the
+//! change by exiting with an error. `on-*` hooks run after it was written,
+//! their exit status is ignored.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{Error, Result, Task};
+
+/// When a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    PreSave,
+    Add,
+    Complete,
+    Remove,
+    Edit,
+}
+
+impl Event {
+    /// The name of the hook, as used in the configuration
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::PreSave => "pre-save",
+            Self::Add => "on-add",
+            Self::Complete => "on-complete",
+            Self::Remove => "on-remove",
+            Self::Edit => "on-edit",
+        }
+    }
+
+    /// What happened to a task, without the `on-` prefix
+    #[must_use]
+    pub const fn change(self) -> &'static str {
+        match self {
+            Self::PreSave => "save",
+            Self::Add => "add",
+            Self::Complete => "complete",
+            Self::Remove => "remove",
+            Self::Edit => "edit",
+        }
+    }
+}
+
+/// The commands to run for every event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hooks {
+    pub pre_save: Vec<String>,
+    pub on_add: Vec<String>,
+    pub on_complete: Vec<String>,
+    pub on_remove: Vec<String>,
+    pub on_edit: Vec<String>,
+}
+
+impl Hooks {
+    #[must_use]
+    pub fn commands(&self, event: Event) -> &[String] {
+        match event {
+            Event::PreSave => &self.pre_save,
+            Event::Add => &self.on_add,
+            Event::Complete => &self.on_complete,
+            Event::Remove => &self.on_remove,
+            Event::Edit => &self.on_edit,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.pre_save.is_empty()
+            && self.on_add.is_empty()
+            && self.on_complete.is_empty()
+            && self.on_remove.is_empty()
+            && self.on_edit.is_empty()
+    }
+}
+
+/// Hooks from the user configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    /// Run for every project
+    pub global: Hooks,
+    /// Whether hooks from project files run as well. They are off by default,
+    /// project files come with every cloned repository.
+    pub project_hooks: bool,
+}
+
+/// Compares the tasks of a project before and after a change. A task that was
+/// completed counts as completed, even if it was edited as well.
+pub(crate) fn changes(before: &[Task], after: &[Task]) -> Vec<(Event, Task)> {
+    let mut changes = Vec::new();
+
+    for task in after {
+        match before.iter().find(|t| t.id == task.id) {
+            None => changes.push((Event::Add, task.clone())),
+            Some(old) if task.completed && !old.completed => {
+                changes.push((Event::Complete, task.clone()));
+            }
+            Some(old) if old != task => changes.push((Event::Edit, task.clone())),
+            Some(_) => {}
+        }
+    }
+
+    for task in before {
+        if !after.iter().any(|t| t.id == task.id) {
+            changes.push((Event::Remove, task.clone()));
+        }
+    }
+
+    changes
+}
+
+/// Runs `command` for a change to `task` in the project `name`, saved at
+/// `path`. Returns whether the command succeeded.
+pub(crate) fn run(
+    command: &str,
+    event: Event,
+    change: Event,
+    name: &str,
+    path: &Path,
+    task: &Task,
+) -> Result<bool> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let input = serde_json::to_vec(task).map_err(|e| Error::Hook {
+        command: command.to_owned(),
+        source: e.into(),
+    })?;
+
+    let run = || -> io::Result<bool> {
+        let mut child = shell(command)
+            .current_dir(dir)
+            .env("TUTEL_EVENT", event.name())
+            .env("TUTEL_CHANGE", change.change())
+            .env("TUTEL_PROJECT", name)
+            .env("TUTEL_PROJECT_FILE", path)
+            .stdin(Stdio::piped())
+            // keep stdout clean for output meant to be parsed
+            .stdout(io::stderr())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            match stdin.write_all(&input) {
+                // the hook doesn't care about the task
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+                result => result?,
+            }
+        }
+
+        Ok(child.wait()?.success())
+    };
+
+    run().map_err(|source| Error::Hook {
+        command: command.to_owned(),
+        source,
+    })
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changes, Event};
+    use crate::Task;
+
+    #[test]
+    fn find_changes() {
+        let kept = Task::new("kept", false, 0);
+        let mut edited = Task::new("edited", false, 1);
+        let mut completed = Task::new("completed", false, 2);
+        let removed = Task::new("removed", false, 3);
+        let before = vec![
+            kept.clone(),
+            edited.clone(),
+            completed.clone(),
+            removed.clone(),
+        ];
+
+        edited.desc = String::from("edited!");
+        completed.set_completed(true);
+        completed.desc = String::from("completed!");
+        let added = Task::new("added", true, 4);
+        let after = vec![added.clone(), kept, edited.clone(), completed.clone()];
+
+        assert_eq!(
+            changes(&before, &after),
+            vec![
+                (Event::Add, added),
+                (Event::Edit, edited),
+                (Event::Complete, completed),
+                (Event::Remove, removed),
+            ]
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! Conversion between projects and iCalendar (RFC 5545) `VTODO` components.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::{project::now, Project, Result, Task};
+
+const DATE_FORMAT: &str = "%Y%m%d";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const MAX_LINE_LENGTH: usize = 75;
+
+/// A to-do read from an iCalendar file
+#[derive(Debug, PartialEq, Eq)]
+pub struct Todo {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub due: Option<NaiveDate>,
+    pub categories: Vec<String>,
+}
+
+/// How many tasks were added/updated by [`merge`]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub added: usize,
+    pub updated: usize,
+}
+
+/// Renders a project and all of its children as an iCalendar file with one
+/// `VTODO` per task. `stamp` is used as `DTSTAMP` for every component.
+#[must_use]
+pub fn render(project: &Project, stamp: DateTime<Utc>) -> String {
+    let mut out = String::new();
+
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(
+        &mut out,
+        concat!(
+            "PRODID:-//tutel//tutel v",
+            env!("CARGO_PKG_VERSION"),
+            "//EN"
+        ),
+    );
+    render_todos(project, stamp, &mut out);
+    push_line(&mut out, "END:VCALENDAR");
+
+    out
+}
+
+fn render_todos(project: &Project, stamp: DateTime<Utc>, out: &mut String) {
+    for task in &project.data.tasks {
+        push_line(out, "BEGIN:VTODO");
+        push_line(out, &format!("UID:{}", escape(&task.id)));
+        push_line(out, &format!("DTSTAMP:{}", stamp.format(DATE_TIME_FORMAT)));
+        push_line(out, &format!("SUMMARY:{}", escape(&task.desc)));
+
+        if task.completed {
+            push_line(out, "STATUS:COMPLETED");
+        } else {
+            push_line(out, "STATUS:NEEDS-ACTION");
+        }
+
+        if let Some(due) = task.due {
+            push_line(out, &format!("DUE;VALUE=DATE:{}", due.format(DATE_FORMAT)));
+        }
+
+        if let Some(completed_at) = task.completed_at {
+            push_line(
+                out,
+                &format!("COMPLETED:{}", completed_at.format(DATE_TIME_FORMAT)),
+            );
+        }
+
+        if !task.tags.is_empty() {
+            let categories: Vec<_> = task.tags.iter().map(|t| escape(t)).collect();
+            push_line(out, &format!("CATEGORIES:{}", categories.join(",")));
+        }
+
+        push_line(out, "END:VTODO");
+    }
+
+    for child in project.get_children() {
+        render_todos(child, stamp, out);
+    }
+}
+
+/// Appends a content line, folding it after 75 octets as required by the spec
+fn push_line(out: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_LENGTH {
+            out.push_str("\r\n ");
+            // the leading space counts towards the line length
+            len = 1;
+        }
+
+        out.push(c);
+        len += c.len_utf8();
+    }
+
+    out.push_str("\r\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                result.push('\\');
+                result.push(c);
+            }
+            '\n' => result.push_str("\\n"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Parses all `VTODO` components of an iCalendar file. Unknown properties and
+/// components are ignored, as are to-dos without a summary.
+#[must_use]
+pub fn parse(input: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut components: Vec<String> = Vec::new();
+    let mut current: Option<Todo> = None;
+
+    for line in unfold(input) {
+        let Some((name, value)) = split_line(&line) else {
+            continue;
+        };
+
+        match name.as_str() {
+            "BEGIN" => {
+                let component = value.to_ascii_uppercase();
+                if component == "VTODO" {
+                    current = Some(Todo {
+                        uid: None,
+                        summary: String::new(),
+                        completed: false,
+                        completed_at: None,
+                        due: None,
+                        categories: Vec::new(),
+                    });
+                }
+                components.push(component);
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VTODO") {
+                    if let Some(todo) = current.take().filter(|t| !t.summary.is_empty()) {
+                        todos.push(todo);
+                    }
+                }
+            }
+            _ => {
+                // properties of components nested in a VTODO (e.g. VALARM) are skipped
+                if components.last().map(String::as_str) != Some("VTODO") {
+                    continue;
+                }
+
+                if let Some(todo) = current.as_mut() {
+                    parse_property(todo, &name, value);
+                }
+            }
+        }
+    }
+
+    todos
+}
+
+fn parse_property(todo: &mut Todo, name: &str, value: &str) {
+    match name {
+        "UID" => todo.uid = Some(unescape(value)),
+        "SUMMARY" => todo.summary = unescape(value).trim().to_string(),
+        "STATUS" => todo.completed = value.eq_ignore_ascii_case("COMPLETED"),
+        "DUE" => {
+            todo.due = parse_date_time(value)
+                .map(|d| d.date_naive())
+                .or_else(|| parse_date(value));
+        }
+        "COMPLETED" => {
+            todo.completed_at = parse_date_time(value);
+            if todo.completed_at.is_some() {
+                todo.completed = true;
+            }
+        }
+        "CATEGORIES" => {
+            todo.categories.extend(
+                split_unescaped(value, ',')
+                    .iter()
+                    .map(|c| unescape(c).trim().to_string())
+                    .filter(|c| !c.is_empty()),
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Joins folded lines back together
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in input.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if let Some(continuation) = line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(continuation);
+                continue;
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    lines
+}
+
+/// Splits a content line into its uppercased name and its value. Parameters
+/// are dropped.
+fn split_line(line: &str) -> Option<(String, &str)> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let name = head.split(';').next().unwrap_or(head);
+
+    Some((name.trim().to_ascii_uppercase(), value))
+}
+
+fn split_unescaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == separator {
+            parts.push(&value[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+
+    parts.push(&value[start..]);
+    parts
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // tasks are single line
+            Some('n' | 'N') => result.push(' '),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), DATE_FORMAT).ok()
+}
+
+/// Parses a UTC or floating date-time. Floating times are treated as UTC.
+fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    let value = value.strip_suffix('Z').unwrap_or(value);
+
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|d| Utc.from_utc_datetime(&d))
+}
+
+/// Applies to-dos to a project tree.
+///
+/// To-dos whose UID matches the id of a task anywhere in the tree update that
+/// task, all others are added to `project` with an id derived from their UID,
+/// see [`id_for_uid`].
+///
+/// # Errors
+/// This function will return an error if `project` runs out of indices.
+pub fn merge(project: &mut Project, todos: Vec<Todo>) -> Result<MergeSummary> {
+    let mut summary = MergeSummary::default();
+
+    for todo in todos {
+        let id = todo.uid.as_deref().map(id_for_uid);
+        let existing = id.as_deref().and_then(|id| find_by_id(project, id));
+
+        if let Some(task) = existing {
+            apply(task, todo);
+            summary.updated += 1;
+        } else {
+            let task = project.add(String::new(), false)?;
+            if let Some(id) = id {
+                task.id = id;
+            }
+            apply(task, todo);
+            summary.added += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn apply(task: &mut Task, todo: Todo) {
+    task.desc = todo.summary;
+    task.due = todo.due;
+    task.tags = todo.categories;
+
+    if todo.completed {
+        if !task.completed || todo.completed_at.is_some() {
+            task.completed_at = Some(todo.completed_at.unwrap_or_else(now));
+        }
+    } else {
+        task.completed_at = None;
+    }
+    task.completed = todo.completed;
+}
+
+/// The task id for a UID.
+///
+/// UIDs that are UUIDs, as tutel exports them, are the id as is. Others are
+/// hashed into one, so ids stay lowercase hex without `:` and importing the
+/// same to-do again still finds it.
+#[must_use]
+pub fn id_for_uid(uid: &str) -> String {
+    Uuid::try_parse(uid).map_or_else(
+        |_| Uuid::new_v5(&Uuid::NAMESPACE_OID, uid.as_bytes()).to_string(),
+        |id| id.to_string(),
+    )
+}
+
+fn find_by_id<'a>(project: &'a mut Project, id: &str) -> Option<&'a mut Task> {
+    if let Some(i) = project.data.tasks.iter().position(|t| t.id == id) {
+        return Some(&mut project.data.tasks[i]);
+    }
+
+    project
+        .get_children_mut()
+        .iter_mut()
+        .find_map(|child| find_by_id(child, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{id_for_uid, merge, parse, render, MergeSummary, Todo};
+    use crate::Project;
+
+    fn project() -> Project {
+        let mut root = Project::new(PathBuf::new(), 0, String::from("root"), false);
+        let mut child = Project::new(PathBuf::new(), 1, String::from("child"), true);
+
+        let task = root
+            .add("buy milk, eggs; bread".to_string(), false)
+            .unwrap();
+        task.id = String::from("a3f1c2d4-0000-4000-8000-000000000001");
+        task.due = NaiveDate::from_ymd_opt(2026, 10, 20);
+        task.tags = vec![String::from("home"), String::from("errands")];
+
+        let task = child.add("write tests".to_string(), true).unwrap();
+        task.id = String::from("a3f1c2d4-0000-4000-8000-000000000002");
+        task.completed_at = Some(Utc.with_ymd_and_hms(2026, 10, 18, 12, 30, 0).unwrap());
+
+        root.attach_child(child);
+        root
+    }
+
+    #[test]
+    fn render_todos() {
+        let stamp = Utc.with_ymd_and_hms(2026, 10, 18, 13, 0, 0).unwrap();
+
+        assert_eq!(
+            render(&project(), stamp),
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                concat!(
+                    "PRODID:-//tutel//tutel v",
+                    env!("CARGO_PKG_VERSION"),
+                    "//EN"
+                ),
+                "BEGIN:VTODO",
+                "UID:a3f1c2d4-0000-4000-8000-000000000001",
+                "DTSTAMP:20261018T130000Z",
+                "SUMMARY:buy milk\\, eggs\\; bread",
+                "STATUS:NEEDS-ACTION",
+                "DUE;VALUE=DATE:20261020",
+                "CATEGORIES:home,errands",
+                "END:VTODO",
+                "BEGIN:VTODO",
+                "UID:a3f1c2d4-0000-4000-8000-000000000002",
+                "DTSTAMP:20261018T130000Z",
+                "SUMMARY:write tests",
+                "STATUS:COMPLETED",
+                "COMPLETED:20261018T123000Z",
+                "END:VTODO",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+
+    #[test]
+    fn fold_long_lines() {
+        let mut root = Project::new(PathBuf::new(), 0, String::from("root"), false);
+        root.add("ä".repeat(60), false).unwrap();
+
+        let rendered = render(&root, Utc::now());
+        assert!(rendered.split("\r\n").all(|l| l.len() <= 75));
+
+        let todos = parse(&rendered);
+        assert_eq!(todos[0].summary, "ä".repeat(60));
+    }
+
+    #[test]
+    fn parse_todos() {
+        let input = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+SUMMARY:not a todo\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:abc-123\r
+SUMMARY:long\r
+  summary\\, folded\r
+DUE:20261101T090000Z\r
+CATEGORIES:a,b\\,c\r
+BEGIN:VALARM\r
+SUMMARY:alarm\r
+END:VALARM\r
+END:VTODO\r
+BEGIN:VTODO\r
+STATUS:COMPLETED\r
+SUMMARY:done\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+
+        assert_eq!(
+            parse(input),
+            vec![
+                Todo {
+                    uid: Some(String::from("abc-123")),
+                    summary: String::from("long summary, folded"),
+                    completed: false,
+                    completed_at: None,
+                    due: NaiveDate::from_ymd_opt(2026, 11, 1),
+                    categories: vec![String::from("a"), String::from("b,c")],
+                },
+                Todo {
+                    uid: None,
+                    summary: String::from("done"),
+                    completed: true,
+                    completed_at: None,
+                    due: None,
+                    categories: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reimport_updates() {
+        let mut project = project();
+        let exported =
+            render(&project, Utc::now()).replace("STATUS:NEEDS-ACTION", "STATUS:COMPLETED");
+
+        let summary = merge(&mut project, parse(&exported)).unwrap();
+        assert_eq!(
+            summary,
+            MergeSummary {
+                added: 0,
+                updated: 2
+            }
+        );
+        assert_eq!(project.data.tasks.len(), 1);
+        assert!(project.data.tasks[0].completed);
+        assert!(project.data.tasks[0].completed_at.is_some());
+
+        let foreign = "BEGIN:VTODO\nUID:foreign\nSUMMARY:from elsewhere\nEND:VTODO\n";
+        assert_eq!(merge(&mut project, parse(foreign)).unwrap().added, 1);
+        assert_eq!(merge(&mut project, parse(foreign)).unwrap().updated, 1);
+        assert_eq!(project.data.tasks.len(), 2);
+        assert_eq!(project.data.tasks[1].id, id_for_uid("foreign"));
+        assert!(!project.data.tasks[1].id.contains(':'));
+
+        assert_eq!(
+            id_for_uid("A3F1C2D4-0000-4000-8000-000000000001"),
+            "a3f1c2d4-0000-4000-8000-000000000001"
+        );
+        assert_ne!(id_for_uid("foreign"), id_for_uid("Foreign"));
+    }
+}
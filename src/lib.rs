@@ -4,16 +4,37 @@
 #![warn(clippy::style)]
 #![warn(clippy::pedantic)]
 #![allow(clippy::cast_possible_wrap)]
+#![allow(clippy::needless_raw_string_hashes)]
 
+pub mod agenda;
+pub mod archive;
+pub mod bulk;
+mod de;
+mod error;
+pub mod estimate;
+pub mod hooks;
+pub mod ical;
+pub mod markdown;
+pub mod merge;
 mod project;
+pub mod prompt;
+pub mod rpc;
+pub mod scan;
+pub mod search;
 mod ser;
-mod de;
+pub mod stats;
+pub mod storage;
+pub mod timer;
+pub mod watch;
+pub mod web;
 
-use std::{fs, path::Path};
+use std::{path::Path, sync::Arc};
 
-use anyhow::{anyhow, Result};
+pub use error::{Error, Result};
+pub use project::{Position, Project, ProjectData, SortKey, Task, TaskRef};
+pub use storage::Storage;
 
-pub use project::{Project, Task};
+use storage::FileStorage;
 
 pub const PROJECT_FILE_NAME: &str = ".tutel.toml";
 pub const CHILDREN_DEPTH_LIMIT: usize = 5;
@@ -23,19 +44,44 @@ pub const CHILDREN_DEPTH_LIMIT: usize = 5;
 /// # Errors
 /// Throws an error if the current directory could not be determined
 pub fn new_project(name: String, is_child: bool) -> Result<Project> {
-    let dir = std::env::current_dir()?;
+    let dir = std::env::current_dir().map_err(|e| Error::io(".", e))?;
     let path = dir.join(PROJECT_FILE_NAME);
 
     Ok(Project::new(path, 0, name, is_child))
 }
 
+/// Loads the closest root project above `path` together with all of its
+/// children. Project files on the way that can't be loaded are skipped.
+///
+/// # Errors
+/// This function will return an error if no root project could be found. If
+/// a project file on the way could not be loaded, that error is returned
+/// instead of [`Error::NoProject`].
 pub fn load_project_rec(path: &Path) -> Result<Project> {
+    let storage: Arc<dyn Storage> = Arc::new(FileStorage);
+    load_project_rec_from(&storage, path)
+}
+
+/// Like [`load_project_rec`], but loads the projects from `storage`.
+///
+/// # Errors
+/// This function will return an error if no root project could be found, see
+/// [`load_project_rec`].
+pub fn load_project_rec_from(storage: &Arc<dyn Storage>, path: &Path) -> Result<Project> {
     let mut root = None;
     let mut dir = None;
+    let mut skipped = None;
     for (steps, p) in path.ancestors().enumerate() {
-        if has_project(p) {
-            let Ok(project) = Project::load(p.join(PROJECT_FILE_NAME), -(steps as isize)) else {
-                continue;
+        let project_file = p.join(PROJECT_FILE_NAME);
+        if storage.exists(&project_file) {
+            // a broken project file doesn't keep the projects above from loading
+            let project = match Project::load_from(storage.clone(), project_file, -(steps as isize))
+            {
+                Ok(project) => project,
+                Err(e) => {
+                    skipped.get_or_insert(e);
+                    continue;
+                }
             };
 
             if !project.is_child() {
@@ -46,48 +92,50 @@ pub fn load_project_rec(path: &Path) -> Result<Project> {
         }
     }
 
-    let mut root = root.ok_or_else(|| anyhow!("no project found"))?;
-    let dir = dir.ok_or_else(|| anyhow!("no project found"))?.to_owned();
+    let (Some(mut root), Some(dir)) = (root, dir) else {
+        return Err(skipped.unwrap_or_else(|| Error::NoProject(path.to_path_buf())));
+    };
+    let dir = dir.to_owned();
     let steps = root.steps();
 
-    load_project_rec_impl(&dir, &mut root, CHILDREN_DEPTH_LIMIT, steps);
+    load_project_rec_impl(storage, &dir, &mut root, CHILDREN_DEPTH_LIMIT, steps);
 
     Ok(root)
 }
 
-fn load_project_rec_impl(path: &Path, parent: &mut Project, limit: usize, steps: isize) {
+fn load_project_rec_impl(
+    storage: &Arc<dyn Storage>,
+    path: &Path,
+    parent: &mut Project,
+    limit: usize,
+    steps: isize,
+) {
     if limit == 0 {
         return;
     }
 
-    let Ok(iter) = fs::read_dir(path) else {
-        return;
-    };
+    // projects right below `path` come before those further down, so the
+    // order of the children doesn't depend on the names of plain directories
+    let mut plain_dirs = Vec::new();
+    for child_path in storage.subdirs(path) {
+        let project_file = child_path.join(PROJECT_FILE_NAME);
 
-    for child_path in iter {
-        let Ok(child_path) = child_path else {
-            continue;
-        };
-
-        let project_file = child_path.path().join(PROJECT_FILE_NAME);
-
-        if let Ok(mut child) = Project::load(project_file, steps + 1) {
+        if let Ok(mut child) = Project::load_from(storage.clone(), project_file, steps + 1) {
             if !child.is_child() {
                 continue;
             }
 
-            load_project_rec_impl(
-                child_path.path().as_path(),
-                &mut child,
-                limit - 1,
-                steps + 1,
-            );
+            load_project_rec_impl(storage, &child_path, &mut child, limit - 1, steps + 1);
 
             parent.attach_child(child);
         } else {
-            load_project_rec_impl(child_path.path().as_path(), parent, limit - 1, steps + 1);
+            plain_dirs.push(child_path);
         }
     }
+
+    for dir in plain_dirs {
+        load_project_rec_impl(storage, &dir, parent, limit - 1, steps + 1);
+    }
 }
 
 /// Determines whether a project exists in the given path by checking
@@ -105,7 +153,7 @@ mod tests {
 
     use std::fs;
 
-    use crate::{load_project_rec, Project, PROJECT_FILE_NAME};
+    use crate::{load_project_rec, Error, Project, PROJECT_FILE_NAME};
 
     const ROOT_CONTENT: &str = r#"
         name = 'root'
@@ -191,4 +239,20 @@ mod tests {
             expected
         );
     }
+
+    #[test]
+    fn skip_broken_project_files() {
+        let tmpdir = setup_tmpdir();
+        let level2 = tmpdir.path().join("level1/level2");
+        fs::write(level2.join(PROJECT_FILE_NAME), "name = ").unwrap();
+
+        let root = load_project_rec(&level2).unwrap();
+        assert_eq!(root.data.name, "root");
+
+        fs::write(tmpdir.path().join(PROJECT_FILE_NAME), "name = ").unwrap();
+        assert!(matches!(
+            load_project_rec(&level2),
+            Err(Error::Parse { .. })
+        ));
+    }
 }
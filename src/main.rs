@@ -2,111 +2,416 @@
 #![warn(clippy::style)]
 #![warn(clippy::nursery)]
 
-use app::{App, Command, TaskSelector};
+use alias::Aliases;
+use app::{App, ArchiveAction, Command, Confirm, Format, MoveTo, ShowOptions, TaskSelector};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use config::Config;
 use owo_colors::OwoColorize;
-use std::{fs, io::Write};
+use std::{
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tempfile::NamedTempFile;
-use tutel::{Project, Task};
+use theme::{ColorChoice, Theme};
+use tutel::{
+    archive::{Archive, ArchivedTask},
+    estimate::{Estimate, Rollup},
+    hooks::Settings as HookSettings,
+    scan::CodeMarker,
+    stats::{Counts, ProjectStats, Stats},
+    timer::ActiveTimer,
+    Position, Project, SortKey, Task, TaskRef,
+};
 
 use anyhow::{bail, Context, Result};
 
+mod alias;
 mod app;
+mod config;
+mod external;
+mod theme;
 
 fn main() {
-    match run_app(app::parse_cli()) {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("{} {}", "[tutel]".red(), e,);
+    // errors are reported once the colors are known
+    let config = Config::load();
+    let (aliases, alias_error) = match config
+        .as_ref()
+        .map(|c| Aliases::from_config(c.section("alias")))
+    {
+        Ok(Ok(aliases)) => (aliases, None),
+        Ok(Err(e)) => (Aliases::default(), Some(e)),
+        Err(_) => (Aliases::default(), None),
+    };
 
-            if e.chain().len() > 1 {
-                eprintln!("\t{}", e.root_cause());
-            }
-            std::process::exit(1);
-        }
+    let app = match app::parse_cli(&aliases) {
+        Ok(app) => app,
+        Err(e) => exit_with(
+            &anyhow::anyhow!(e),
+            ColorChoice::Auto.enabled(io::stderr().is_terminal()),
+        ),
+    };
+
+    // errors would end up in every prompt, so config errors are ignored there
+    if let Command::Prompt { format, timeout } = app.cmd {
+        prompt(format, timeout, config.ok().as_ref());
+        return;
+    }
+
+    let color = app
+        .color
+        .unwrap_or(ColorChoice::Auto)
+        .enabled(io::stderr().is_terminal());
+
+    if let Some(e) = alias_error {
+        exit_with(
+            &anyhow::anyhow!("invalid [alias] section in config file: {e}"),
+            color,
+        );
+    }
+
+    if let Err(e) = config.and_then(|config| run_app(app, &config, &aliases)) {
+        exit_with(&e, color);
     }
 }
 
-fn run_app(app: App) -> Result<()> {
+/// Reports `e` the way every error is reported and exits
+fn exit_with(e: &anyhow::Error, color: bool) -> ! {
+    let prefix = if color {
+        "[tutel]".red().to_string()
+    } else {
+        String::from("[tutel]")
+    };
+    eprintln!("{} {}", prefix, e,);
+
+    if e.chain().len() > 1 {
+        eprintln!("\t{}", e.root_cause());
+    }
+    std::process::exit(1);
+}
+
+fn run_app(app: App, config: &Config, aliases: &Aliases) -> Result<()> {
+    let theme = Theme::from_config(
+        config.section("theme"),
+        app.color,
+        app.ascii,
+        io::stdout().is_terminal(),
+    )?;
     let cmd = app.cmd;
+    let confirm = app.confirm;
 
     // Project Independent Commands
     if let Command::NewProject { name, force } = cmd {
-        new_project(name, force)?;
+        new_project(name, force, confirm)?;
         return Ok(());
-    } else if cmd == Command::RemoveProject {
-        remove_project()?;
+    } else if let Command::RemoveProject { recursive } = cmd {
+        remove_project(recursive, confirm)?;
         return Ok(());
+    } else if let Command::MergeDriver { base, ours, theirs } = cmd {
+        tutel::merge::merge_files(&base, &ours, &theirs)?;
+        return Ok(());
+    } else if cmd == Command::InstallMergeDriver {
+        return install_merge_driver();
+    } else if cmd == Command::StopTimer {
+        return stop_timer(&hook_settings(config)?, confirm);
+    } else if let Command::Watch { poll } = cmd {
+        return watch(poll, &theme);
+    } else if let Command::External { name, args } = cmd {
+        return external(&name, &args, aliases);
+    } else if let Command::Serve { socket } = cmd {
+        return serve(&socket, hook_settings(config)?);
+    } else if let Command::Web { listen } = cmd {
+        return web(&listen, hook_settings(config)?);
+    } else if let Command::Search {
+        pattern,
+        regex,
+        ignore_case,
+        all,
+    } = cmd
+    {
+        return search(&pattern, regex, ignore_case, all, config, &theme);
+    } else if let Command::Agenda { days, all } = cmd {
+        return agenda(&load_projects(all, config)?, days, &theme);
     }
 
-    let p = tutel::load_project_rec(&std::env::current_dir()?)?;
+    let dir = std::env::current_dir()?;
+    let mut p = tutel::load_project_rec(&dir)?;
+    p.set_hooks(&Arc::new(hook_settings(config)?));
+
+    for name in p.duplicate_names() {
+        eprintln!("[tutel] warning: more than one project is named {name}, use `tutel rename` to tell them apart");
+    }
 
     //Run Commands
     match cmd {
-        Command::Show => print_list(p),
-        Command::AddTask { desc, completed } => add(p, desc, completed),
-        Command::MarkCompletion(completed, selector) => done(p, selector, completed),
-        Command::RemoveTask(selector) => remove(p, selector),
-        Command::EditTask(editor, index) => edit_task(p, index, editor),
+        Command::Show(options) => print_list(p, &options, &theme),
+        Command::AddTask {
+            desc,
+            completed,
+            due,
+            tags,
+            priority,
+            estimate,
+        } => {
+            add(&mut p, desc, completed, due, tags, priority, estimate)?;
+            save(&mut p, confirm, &theme)
+        }
+        Command::BulkAdd { source, metadata } => bulk_add(p, source, metadata, confirm, &theme),
+        Command::MarkCompletion(completed, selector) => {
+            done(p, selector, completed, confirm, &theme)
+        }
+        Command::RemoveTask(selector) => remove(p, selector, config, confirm, &theme),
+        Command::EditTask(editor, index) => edit_task(p, index, editor, confirm, &theme),
+        Command::EstimateTask(index, estimate) => {
+            estimate_task(p, &index, estimate, confirm, &theme)
+        }
+        Command::MoveTask(index, to) => move_task(p, &index, to, confirm, &theme),
+        Command::SortTasks(by) => sort_tasks(p, by, confirm, &theme),
+        Command::RenameProject(name) => rename(p, &dir, name, confirm),
+        Command::ProjectInfo => project_info(&p, &dir, &theme),
+        Command::Export { format, indices } => export(&p, format, indices),
+        Command::Scan => scan(p, confirm, &theme),
+        Command::Import {
+            format,
+            file,
+            headings,
+        } => import(p, format, file, headings, confirm, &theme),
+        Command::Archive(action) => archive(p, action, config, confirm, &theme),
+        Command::Stats { json } => stats(&p, json, config, &theme),
+        Command::StartTimer(index) => start_timer(p, &index, confirm),
+        Command::Report { week, csv } => report(&p, week, csv, &theme),
         _ => unreachable!(),
     }
 }
 
-fn print_list(p: Project) -> Result<()> {
-    println!("{}", stringify_project(&p));
+fn print_list(p: Project, options: &ShowOptions, theme: &Theme) -> Result<()> {
+    if let Some(budget) = options.budget {
+        print_budget(&p, budget, theme);
+    } else {
+        println!("{}", stringify_project(&p, options, theme));
+    }
+
+    Ok(())
+}
+
+fn print_budget(p: &Project, budget: Estimate, theme: &Theme) {
+    let picked = tutel::estimate::budget(p, budget);
+    if picked.is_empty() {
+        println!("{}", theme.glyphs.empty);
+        return;
+    }
+
+    // every picked task is estimated in the unit of the budget
+    let mut planned = 0;
+    for entry in &picked {
+        if let Some(Estimate::Minutes(v) | Estimate::Points(v)) = entry.task.estimate {
+            planned += v;
+        }
+
+        println!(
+            "{}:{:03} {} {} {}",
+            entry.project,
+            entry.task.index,
+            theme.bold(&theme.glyphs.separator),
+            entry.task.desc,
+            theme.dim(
+                &entry
+                    .task
+                    .estimate
+                    .map(|e| e.to_string())
+                    .unwrap_or_default()
+            )
+        );
+    }
+
+    let planned = match budget {
+        Estimate::Minutes(_) => Estimate::Minutes(planned),
+        Estimate::Points(_) => Estimate::Points(planned),
+    };
+    println!("\n{} {} of {}", theme.bold("planned:"), planned, budget);
+}
+
+fn add(
+    p: &mut Project,
+    desc: String,
+    completed: bool,
+    due: Option<NaiveDate>,
+    tags: Vec<String>,
+    priority: Option<u8>,
+    estimate: Option<Estimate>,
+) -> Result<()> {
+    let task = p.add(desc, completed)?;
+    task.due = due;
+    task.tags = tags;
+    task.priority = priority;
+    task.estimate = estimate;
 
     Ok(())
 }
 
-fn add(mut p: Project, desc: String, completed: bool) -> Result<()> {
-    p.add(desc, completed);
+fn bulk_add(
+    mut p: Project,
+    source: Option<PathBuf>,
+    metadata: bool,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let input = read_input(source)?;
+    let items = tutel::bulk::parse(&input, metadata);
+    let indices = tutel::bulk::add_all(&mut p, items)?;
+    if !proceed(&p, confirm, false, theme)? {
+        return Ok(());
+    }
     p.save()?;
+
+    for index in indices {
+        let task = p.get_task_mut(index)?;
+        println!("{}", stringify_task(task, &ShowOptions::default(), theme));
+    }
+
     Ok(())
 }
 
-fn done(mut p: Project, selector: TaskSelector, completed: bool) -> Result<()> {
+fn estimate_task(
+    mut p: Project,
+    index: &TaskRef,
+    estimate: Option<Estimate>,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let (project, index) = p.resolve(index)?;
+    project.get_task_mut(index)?.estimate = estimate;
+
+    save(&mut p, confirm, theme)
+}
+
+fn done(
+    mut p: Project,
+    selector: TaskSelector,
+    completed: bool,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let bulk = selector == TaskSelector::All;
     match selector {
         TaskSelector::Indexed(indices) => {
             for index in indices {
-                p.mark_completion(index, completed)?;
+                let (project, index) = p.resolve(&index)?;
+                project.mark_completion(index, completed)?;
             }
         }
         TaskSelector::All => p.mark_completion_all(completed),
         TaskSelector::Completed => unreachable!(),
     }
 
-    p.save()?;
+    if proceed(&p, confirm, bulk, theme)? {
+        p.save()?;
+    }
 
     Ok(())
 }
 
-fn remove(mut p: Project, selector: TaskSelector) -> Result<()> {
+fn remove(
+    mut p: Project,
+    selector: TaskSelector,
+    config: &Config,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let bulk = !matches!(selector, TaskSelector::Indexed(_));
+    let mut archived = Vec::new();
     match selector {
         TaskSelector::Indexed(indices) => {
-            for index in indices {
-                p.remove(index);
+            // resolve everything first, removing may make id prefixes unambiguous
+            let ids = indices
+                .iter()
+                .map(|index| {
+                    let (project, index) = p.resolve(index)?;
+                    Ok(TaskRef::Id(project.get_task_mut(index)?.id.clone()))
+                })
+                .collect::<tutel::Result<Vec<_>>>()?;
+
+            for id in ids {
+                let (project, index) = p.resolve(&id)?;
+                project.remove(index);
             }
         }
         TaskSelector::All => p.remove_all(),
-        TaskSelector::Completed => p.remove_completed(),
+        TaskSelector::Completed => {
+            let cleanup = config
+                .section("archive")
+                .and_then(|s| s.get("cleanup"))
+                .and_then(toml::Value::as_bool);
+
+            if cleanup == Some(true) {
+                archived = tutel::archive::take_completed(&mut p);
+            } else {
+                p.remove_completed();
+            }
+        }
+    }
+
+    if !proceed(&p, confirm, bulk, theme)? {
+        return Ok(());
     }
 
+    if !archived.is_empty() {
+        // archive first, tasks may be archived twice but never lost
+        open_archive(config)?.append(&archived)?;
+    }
     p.save()?;
 
     Ok(())
 }
 
-fn remove_project() -> Result<()> {
+/// Removes the project file of the nearest project, with `recursive` also the
+/// ones of all projects above it
+fn remove_project(recursive: bool, confirm: Confirm) -> Result<()> {
+    let mut files = Vec::new();
     for path in std::env::current_dir()?.ancestors() {
-        if let Some(project_file) = tutel::has_project(path) {
-            fs::remove_file(project_file).context("could not delete project file")?
+        if tutel::has_project(path) {
+            files.push(path.join(tutel::PROJECT_FILE_NAME));
+            if !recursive {
+                break;
+            }
+        }
+    }
+
+    if files.is_empty() {
+        bail!("no project found");
+    }
+
+    if confirm.dry_run {
+        for file in &files {
+            println!("remove {}", file.to_string_lossy());
         }
+        return Ok(());
+    }
+
+    if !confirm.yes && io::stdin().is_terminal() {
+        for file in &files {
+            eprintln!("remove {}", file.to_string_lossy());
+        }
+        ask("remove these project files with all of their tasks?")?;
+    }
+
+    for file in files {
+        fs::remove_file(file).context("could not delete project file")?;
     }
 
     Ok(())
 }
 
-fn edit_task(mut p: Project, index: usize, editor: String) -> Result<()> {
-    let task = p.get_task_mut(index)?;
+fn edit_task(
+    mut p: Project,
+    index: TaskRef,
+    editor: String,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let (project, index) = p.resolve(&index)?;
+    let task = project.get_task_mut(index)?;
 
     let mut tmpfile = NamedTempFile::new()?;
     tmpfile.write_all(task.desc.as_bytes())?;
@@ -115,7 +420,7 @@ fn edit_task(mut p: Project, index: usize, editor: String) -> Result<()> {
     let mut cmd = std::process::Command::new(editor.as_str())
         .arg(tmpfile.path())
         .spawn()
-        .context("editor {editor} not found")?;
+        .with_context(|| format!("editor {editor} not found"))?;
 
     cmd.wait()?;
 
@@ -123,15 +428,906 @@ fn edit_task(mut p: Project, index: usize, editor: String) -> Result<()> {
     let new = fs::read_to_string(tmpfile.path())?;
     task.desc = new.replace('\n', " ");
 
+    save(&mut p, confirm, theme)
+}
+
+fn move_task(
+    mut p: Project,
+    index: &TaskRef,
+    to: MoveTo,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let project = p.resolve(index)?.0.path().to_path_buf();
+    let to = match to {
+        MoveTo::Top => Position::Top,
+        MoveTo::Bottom => Position::Bottom,
+        MoveTo::Before(before) => {
+            let (other, before) = p.resolve(&before)?;
+            if other.path() != project {
+                bail!("tasks can only be moved within their project");
+            }
+            Position::Before(before)
+        }
+    };
+
+    let (project, index) = p.resolve(index)?;
+    project.move_task(index, to)?;
+
+    save(&mut p, confirm, theme)
+}
+
+fn sort_tasks(mut p: Project, by: SortKey, confirm: Confirm, theme: &Theme) -> Result<()> {
+    p.sort_tasks(by);
+
+    save(&mut p, confirm, theme)
+}
+
+/// Renames the project `dir` is in. Names have to be unique within the tree,
+/// tasks are selected by them.
+fn rename(mut p: Project, dir: &Path, name: String, confirm: Confirm) -> Result<()> {
+    if nearest_mut(&mut p, dir).data.name == name {
+        return Ok(());
+    }
+    if project_names(&p).contains(&name.as_str()) {
+        bail!("a project named {name} already exists");
+    }
+
+    let target = nearest_mut(&mut p, dir);
+    if confirm.dry_run {
+        println!("rename {} to {name}", target.data.name);
+        return Ok(());
+    }
+
+    target.data.name = name;
+    p.save()?;
+
+    Ok(())
+}
+
+fn project_info(p: &Project, dir: &Path, theme: &Theme) -> Result<()> {
+    let mut parent = None;
+    let mut project = p;
+    while let Some(child) = project.get_children().iter().find(|c| contains(c, dir)) {
+        parent = Some(project);
+        project = child;
+    }
+
+    let label = |label: &str| theme.bold(&format!("{label:<9}"));
+    println!("{} {}", label("name"), project.data.name);
+    println!("{} {}", label("path"), project.path().to_string_lossy());
+    println!("{} {}", label("steps"), project.steps());
+    if let Some(parent) = parent {
+        println!("{} {}", label("parent"), parent.data.name);
+    }
+    println!(
+        "{} {}",
+        label("tasks"),
+        stringify_task_counts(project, theme)
+    );
+
+    if project.get_children().is_empty() {
+        println!("{} -", label("children"));
+    }
+    for (i, child) in project.get_children().iter().enumerate() {
+        println!(
+            "{} {} {}",
+            label(if i == 0 { "children" } else { "" }),
+            child.data.name,
+            theme.dim(&format!("({})", stringify_task_counts(child, theme)))
+        );
+    }
+
+    Ok(())
+}
+
+/// E.g. `2 open, 1 done`, of `p` without its children
+fn stringify_task_counts(p: &Project, theme: &Theme) -> String {
+    let done = p.data.tasks.iter().filter(|t| t.completed).count();
+    let open = p.data.tasks.len() - done;
+
+    format!(
+        "{} open, {} done",
+        theme.open(&open.to_string()),
+        theme.done(&done.to_string())
+    )
+}
+
+/// Whether `dir` is in the directory of `p` or below it
+fn contains(p: &Project, dir: &Path) -> bool {
+    p.path().parent().is_some_and(|d| dir.starts_with(d))
+}
+
+/// The project of the tree `dir` is in, the deepest one containing it
+fn nearest_mut<'a>(p: &'a mut Project, dir: &Path) -> &'a mut Project {
+    match p.get_children().iter().position(|c| contains(c, dir)) {
+        Some(i) => nearest_mut(&mut p.get_children_mut()[i], dir),
+        None => p,
+    }
+}
+
+/// The names of `p` and all of its children
+fn project_names(p: &Project) -> Vec<&str> {
+    let mut names = vec![p.data.name.as_str()];
+    for child in p.get_children() {
+        names.extend(project_names(child));
+    }
+    names
+}
+
+fn scan(mut p: Project, confirm: Confirm, theme: &Theme) -> Result<()> {
+    let summary = tutel::scan::sync(&mut p);
+    // only the comments change, the summary below lists them
+    if !confirm.dry_run {
+        p.save()?;
+    }
+
+    for marker in &summary.added {
+        println!(
+            "{} {}",
+            theme.open("new     "),
+            stringify_code_marker(marker, theme)
+        );
+    }
+
+    for marker in &summary.resolved {
+        println!(
+            "{} {}",
+            theme.done("resolved"),
+            stringify_code_marker(marker, theme)
+        );
+    }
+
+    Ok(())
+}
+
+fn export(p: &Project, format: Format, indices: bool) -> Result<()> {
+    let output = match format {
+        Format::Markdown => tutel::markdown::render(p, indices),
+        Format::Ics => tutel::ical::render(p, Utc::now()),
+    };
+
+    print!("{output}");
+
+    Ok(())
+}
+
+/// Reads `file` or, without one, stdin
+fn read_input(file: Option<PathBuf>) -> Result<String> {
+    if let Some(file) = file {
+        fs::read_to_string(&file)
+            .with_context(|| format!("unable to read {}", file.to_string_lossy()))
+    } else {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("unable to read stdin")?;
+        Ok(input)
+    }
+}
+
+fn import(
+    mut p: Project,
+    format: Format,
+    file: Option<PathBuf>,
+    headings: bool,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let input = read_input(file)?;
+
+    let sections = match format {
+        Format::Markdown => tutel::markdown::parse(&input),
+        Format::Ics => {
+            let summary = tutel::ical::merge(&mut p, tutel::ical::parse(&input))?;
+            if !proceed(&p, confirm, false, theme)? {
+                return Ok(());
+            }
+            p.save()?;
+
+            println!("added {}, updated {}", summary.added, summary.updated);
+            return Ok(());
+        }
+    };
+
+    for section in sections {
+        let target = match section.heading {
+            Some(heading) if headings && heading != p.data.name => child_project(&mut p, heading)?,
+            _ => &mut p,
+        };
+
+        for item in section.items {
+            target.add(item.desc, item.completed)?;
+        }
+    }
+
+    if !proceed(&p, confirm, false, theme)? {
+        return Ok(());
+    }
+
+    for child in p.get_children() {
+        if let Some(dir) = child.path().parent() {
+            fs::create_dir_all(dir).context("unable to create child project directory")?;
+        }
+    }
     p.save()?;
 
     Ok(())
 }
 
+fn archive(
+    mut p: Project,
+    action: ArchiveAction,
+    config: &Config,
+    confirm: Confirm,
+    theme: &Theme,
+) -> Result<()> {
+    let archive = open_archive(config)?;
+
+    match action {
+        ArchiveAction::Store => {
+            let mut entries = Vec::new();
+            take_completed_rec(&mut p, &mut entries);
+            if !proceed(&p, confirm, false, theme)? {
+                return Ok(());
+            }
+
+            // archive first, tasks may be archived twice but never lost
+            archive.append(&entries)?;
+            p.save()?;
+
+            println!("archived {} tasks", entries.len());
+        }
+        ArchiveAction::List | ArchiveAction::Search(_) => {
+            let paths = project_paths(&p);
+            for entry in archive.load()? {
+                if !paths.contains(&entry.project) {
+                    continue;
+                }
+                if let ArchiveAction::Search(query) = &action {
+                    if !entry.matches(query) {
+                        continue;
+                    }
+                }
+
+                println!("{}", stringify_archived(&entry, theme));
+            }
+        }
+        ArchiveAction::Restore(prefixes) => {
+            let paths = project_paths(&p);
+            let entries: Vec<_> = archive
+                .load()?
+                .into_iter()
+                .filter(|e| paths.contains(&e.project))
+                .collect();
+
+            let mut ids = Vec::new();
+            for prefix in &prefixes {
+                let candidates: Vec<_> = entries
+                    .iter()
+                    .filter(|e| e.task.id_starts_with(prefix))
+                    .collect();
+
+                match candidates.as_slice() {
+                    [] => bail!("no archived task with id {prefix} found"),
+                    [entry] => ids.push(entry.task.id.clone()),
+                    _ => bail!("id prefix {prefix} is ambiguous"),
+                }
+            }
+
+            // restore first, the archive still has them if saving fails
+            for entry in entries.iter().filter(|e| ids.contains(&e.task.id)) {
+                let target = find_project_mut(&mut p, &entry.project)
+                    .context("the project of an archived task went away")?;
+                tutel::archive::restore(target, entry.clone())?;
+            }
+            if !proceed(&p, confirm, false, theme)? {
+                return Ok(());
+            }
+            p.save()?;
+
+            archive.take(|e| ids.contains(&e.task.id))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn search(
+    pattern: &str,
+    regex: bool,
+    ignore_case: bool,
+    all: bool,
+    config: &Config,
+    theme: &Theme,
+) -> Result<()> {
+    let query = tutel::search::Query::new(pattern, regex, ignore_case)
+        .with_context(|| format!("invalid pattern: {pattern}"))?;
+
+    let projects = load_projects(all, config)?;
+
+    let mut found = false;
+    for p in &projects {
+        for m in tutel::search::search(p, &query) {
+            found = true;
+            println!(
+                "{}:{:03} {} {}{}",
+                m.project,
+                m.task.index,
+                theme.bold(&theme.glyphs.separator),
+                theme.marker(m.task.completed),
+                highlight(&m.task.desc, &m.ranges, theme)
+            );
+        }
+    }
+
+    if !found {
+        println!("{}", theme.glyphs.empty);
+    }
+
+    Ok(())
+}
+
+/// The project tree of the current directory. With `all`, also the trees of
+/// the projects listed in the `[search]` section of the config.
+fn load_projects(all: bool, config: &Config) -> Result<Vec<Project>> {
+    let current = tutel::load_project_rec(&std::env::current_dir()?);
+    let mut projects = Vec::new();
+    if all {
+        // looking everywhere else also works outside of projects
+        projects.extend(current.ok());
+
+        for dir in search_projects(config)? {
+            match tutel::load_project_rec(&dir) {
+                Ok(p)
+                    if projects
+                        .iter()
+                        .all(|other: &Project| other.path() != p.path()) =>
+                {
+                    projects.push(p);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[tutel] {e}"),
+            }
+        }
+    } else {
+        projects.push(current?);
+    }
+
+    Ok(projects)
+}
+
+/// The directories in `projects` of the `[search]` section of the config,
+/// `~/` stands for the home directory
+fn search_projects(config: &Config) -> Result<Vec<PathBuf>> {
+    let Some(projects) = config.section("search").and_then(|s| s.get("projects")) else {
+        return Ok(Vec::new());
+    };
+
+    let Some(projects) = projects.as_array() else {
+        bail!("search.projects must be a list of directories");
+    };
+
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    projects
+        .iter()
+        .map(|dir| {
+            let dir = dir
+                .as_str()
+                .context("search.projects must be a list of directories")?;
+
+            Ok(match (dir.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(dir),
+            })
+        })
+        .collect()
+}
+
+/// Highlights the byte `ranges` of `text`
+fn highlight(text: &str, ranges: &[std::ops::Range<usize>], theme: &Theme) -> String {
+    let mut result = String::new();
+    let mut end = 0;
+    for range in ranges {
+        result.push_str(&text[end..range.start]);
+        result.push_str(&theme.highlight(&text[range.clone()]));
+        end = range.end;
+    }
+    result.push_str(&text[end..]);
+
+    result
+}
+
+fn agenda(projects: &[Project], days: Option<u32>, theme: &Theme) -> Result<()> {
+    let groups = tutel::agenda::collect_all(projects, Local::now().date_naive(), days);
+
+    let mut result = String::new();
+    for (bucket, entries) in groups {
+        if !result.is_empty() {
+            result.push('\n');
+        }
+
+        let title = if bucket == tutel::agenda::Bucket::Overdue {
+            theme.open(bucket.title())
+        } else {
+            bucket.title().to_string()
+        };
+        result.push_str(&theme.bold(&title));
+        result.push('\n');
+
+        for entry in entries {
+            let due = entry
+                .task
+                .due
+                .map(|due| theme.dim(&format!(" {due}")))
+                .unwrap_or_default();
+
+            result.push_str(&format!(
+                "{}:{:03} {} {}{}\n",
+                entry.project,
+                entry.task.index,
+                theme.bold(&theme.glyphs.separator),
+                entry.task.desc,
+                due
+            ));
+        }
+    }
+
+    if result.is_empty() {
+        println!("{}", theme.glyphs.empty);
+    } else {
+        print!("{result}");
+    }
+
+    Ok(())
+}
+
+fn start_timer(mut p: Project, index: &TaskRef, confirm: Confirm) -> Result<()> {
+    let state = timer_state_file()?;
+    let now = Utc::now();
+
+    if let Some(stopped) = stop_running(&mut p, &state, now, confirm.dry_run)? {
+        if confirm.dry_run {
+            println!("stop {stopped}");
+        } else {
+            println!("stopped {stopped}");
+        }
+    }
+
+    let (project, index) = p.resolve(index)?;
+    let path = project.path().to_path_buf();
+    let task = project.get_task_mut(index)?;
+    let timer = ActiveTimer {
+        project: path,
+        task_id: task.id.clone(),
+        started_at: now,
+    };
+    if confirm.dry_run {
+        println!("start {}", task.desc);
+        return Ok(());
+    }
+    println!("started {}", task.desc);
+
+    // the time of a stopped timer has to be saved before a new one starts
+    p.save()?;
+    timer.save(&state)?;
+
+    Ok(())
+}
+
+fn watch(poll: bool, theme: &Theme) -> Result<()> {
+    let p = tutel::load_project_rec(&std::env::current_dir()?)?;
+    let dir = p
+        .path()
+        .parent()
+        .context("project file has no parent directory")?
+        .to_path_buf();
+
+    let mut watcher = if poll {
+        tutel::watch::Watcher::polling(&dir)
+    } else {
+        tutel::watch::Watcher::new(&dir)
+    };
+
+    let options = ShowOptions::default();
+    let mut loaded = Ok(p);
+    loop {
+        // clear the screen and move the cursor to the top left
+        print!("\x1b[2J\x1b[H");
+        match &loaded {
+            Ok(p) => println!("{}", stringify_project(p, &options, theme)),
+            Err(e) => println!("[tutel] {e}"),
+        }
+        io::stdout().flush()?;
+
+        watcher.wait();
+        loaded = tutel::load_project_rec(&dir);
+    }
+}
+
+fn external(name: &str, args: &[std::ffi::OsString], aliases: &Aliases) -> Result<()> {
+    if name == "help" {
+        return help(args, aliases);
+    }
+
+    let executable = external::find(name).with_context(|| format!("no such command: {name}"))?;
+    // also usable outside of projects, e.g. to create one
+    let p = tutel::load_project_rec(&std::env::current_dir()?).ok();
+
+    let code = external::run(&executable, args, p.as_ref())?;
+    std::process::exit(code);
+}
+
+/// `tutel help [COMMAND]`, like `--help` but also lists external subcommands
+fn help(args: &[std::ffi::OsString], aliases: &Aliases) -> Result<()> {
+    if let Some(name) = args.first() {
+        let name = name.to_string_lossy();
+        if let Some(executable) = external::find(&name) {
+            let code = external::run(&executable, &["--help".into()], None)?;
+            std::process::exit(code);
+        }
+
+        print!("{}", app::help(Some(&name)));
+        return Ok(());
+    }
+
+    print!("{}", app::help(None));
+
+    let external = external::discover();
+    if !external.is_empty() {
+        println!("\nExternal commands:");
+        for name in external {
+            println!("    {name}");
+        }
+    }
+
+    let mut aliases = aliases.iter().peekable();
+    if aliases.peek().is_some() {
+        println!("\nAliases:");
+        for (name, words) in aliases {
+            println!("    {name} = {}", words.join(" "));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the `[hooks]` section of the user configuration
+fn hook_settings(config: &Config) -> Result<HookSettings> {
+    let Some(section) = config.section("hooks") else {
+        return Ok(HookSettings::default());
+    };
+
+    toml::Value::Table(section.clone())
+        .try_into()
+        .context("invalid [hooks] section in config file")
+}
+
+fn prompt(format: Option<String>, timeout: u64, config: Option<&Config>) {
+    let budget = std::time::Duration::from_millis(timeout);
+    let today = Local::now().date_naive();
+
+    // errors would end up in every prompt, print nothing instead
+    let Some(summary) = std::env::current_dir()
+        .ok()
+        .and_then(|dir| tutel::prompt::summary_within(&dir, today, budget))
+    else {
+        return;
+    };
+
+    let format = format.or_else(|| {
+        config?
+            .section("prompt")
+            .and_then(|s| s.get("format"))
+            .and_then(toml::Value::as_str)
+            .map(String::from)
+    });
+
+    print!(
+        "{}",
+        summary.format(format.as_deref().unwrap_or(tutel::prompt::DEFAULT_FORMAT))
+    );
+}
+
+#[cfg(unix)]
+fn serve(socket: &std::path::Path, hooks: HookSettings) -> Result<()> {
+    let server = tutel::rpc::Server::new(&std::env::current_dir()?)?.with_hooks(hooks);
+    eprintln!("listening on {}", socket.to_string_lossy());
+
+    Arc::new(server).serve(socket)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve(_socket: &std::path::Path, _hooks: HookSettings) -> Result<()> {
+    bail!("tutel serve needs Unix sockets, which are not available on this platform")
+}
+
+fn web(listen: &str, hooks: HookSettings) -> Result<()> {
+    let server = tutel::rpc::Server::new(&std::env::current_dir()?)?.with_hooks(hooks);
+    let listener = tutel::web::listen(listen)?;
+    eprintln!("listening on http://{listen}");
+
+    tutel::web::serve(&Arc::new(server), &listener)?;
+    Ok(())
+}
+
+fn stop_timer(hooks: &HookSettings, confirm: Confirm) -> Result<()> {
+    let state = timer_state_file()?;
+    let Some(timer) = ActiveTimer::load(&state)? else {
+        bail!("no timer is running");
+    };
+
+    let mut p = Project::load(timer.project, 0)?;
+    p.set_hooks(&Arc::new(hooks.clone()));
+    if let Some(stopped) = stop_running(&mut p, &state, Utc::now(), confirm.dry_run)? {
+        if confirm.dry_run {
+            println!("stop {stopped}");
+            return Ok(());
+        }
+        println!("stopped {stopped}");
+    }
+    p.save()?;
+
+    Ok(())
+}
+
+/// Stops the running timer, if any, and records its time on the task.
+/// Returns the description and the time tracked in total of that task.
+/// `p` should contain the project of the task to avoid loading it twice.
+/// With `dry_run`, the time is only recorded on `p` and nothing is saved.
+fn stop_running(
+    p: &mut Project,
+    state: &std::path::Path,
+    now: chrono::DateTime<Utc>,
+    dry_run: bool,
+) -> Result<Option<String>> {
+    let Some(timer) = ActiveTimer::load(state)? else {
+        return Ok(None);
+    };
+
+    let stopped = if let Some(target) = find_project_mut(p, &timer.project) {
+        timer.stop(target, now).map(stringify_tracked)
+    } else {
+        let mut other = Project::load(timer.project.clone(), 0)?;
+        let stopped = timer.stop(&mut other, now).map(stringify_tracked);
+        if !dry_run {
+            other.save()?;
+        }
+        stopped
+    };
+
+    if dry_run {
+        return match stopped {
+            Ok(stopped) => Ok(Some(stopped)),
+            Err(e) => bail!("{e}, the timer would be discarded"),
+        };
+    }
+
+    // a timer on a task that is gone would be stuck forever
+    ActiveTimer::clear(state)?;
+
+    match stopped {
+        Ok(stopped) => Ok(Some(stopped)),
+        Err(e) => bail!("{e}, the timer was discarded"),
+    }
+}
+
+fn stringify_tracked(task: &Task) -> String {
+    format!("{} ({})", task.desc, stringify_duration(task.tracked()))
+}
+
+fn timer_state_file() -> Result<PathBuf> {
+    Ok(config::state_dir()
+        .context("unable to determine the state directory")?
+        .join("timer.toml"))
+}
+
+fn report(p: &Project, week: bool, csv: bool, theme: &Theme) -> Result<()> {
+    let since = if week {
+        let today = Local::now().date_naive();
+        let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        Local
+            .from_local_datetime(&monday.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|d| d.with_timezone(&Utc))
+    } else {
+        None
+    };
+
+    let lines = tutel::timer::report(p, since, None);
+
+    if csv {
+        print!("{}", tutel::timer::render_csv(&lines));
+        return Ok(());
+    }
+
+    let mut total = Duration::zero();
+    for line in &lines {
+        total += line.time;
+        println!(
+            "{}:{:03} {} {:>8} {}",
+            line.project,
+            line.task.index,
+            theme.bold(&theme.glyphs.separator),
+            stringify_duration(line.time),
+            line.task.desc
+        );
+    }
+
+    if lines.is_empty() {
+        println!("{}", theme.glyphs.empty);
+    } else {
+        println!("{} {}", theme.bold("total:"), stringify_duration(total));
+    }
+
+    Ok(())
+}
+
+fn stats(p: &Project, json: bool, config: &Config, theme: &Theme) -> Result<()> {
+    let archived = open_archive(config)?.load()?;
+    let stats = tutel::stats::collect(p, &archived, &Local::now());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        print!("{}", stringify_stats(&stats, theme));
+    }
+
+    Ok(())
+}
+
+/// Opens the archive set by `path` in the `[archive]` section of the config,
+/// or the default location in the data directory
+fn open_archive(config: &Config) -> Result<Archive> {
+    let configured = config
+        .section("archive")
+        .and_then(|s| s.get("path"))
+        .and_then(toml::Value::as_str);
+
+    let path = if let Some(path) = configured {
+        PathBuf::from(path)
+    } else {
+        config::data_dir()
+            .context("unable to determine the data directory, set archive.path in the config")?
+            .join("archive.toml")
+    };
+
+    Ok(Archive::new(path))
+}
+
+fn take_completed_rec(p: &mut Project, entries: &mut Vec<ArchivedTask>) {
+    entries.extend(tutel::archive::take_completed(p));
+
+    for child in p.get_children_mut() {
+        take_completed_rec(child, entries);
+    }
+}
+
+/// The project files of `p` and all of its children
+fn project_paths(p: &Project) -> Vec<PathBuf> {
+    let mut paths = vec![p.path().to_path_buf()];
+    for child in p.get_children() {
+        paths.extend(project_paths(child));
+    }
+    paths
+}
+
+fn find_project_mut<'a>(p: &'a mut Project, path: &std::path::Path) -> Option<&'a mut Project> {
+    if p.path() == path {
+        return Some(p);
+    }
+
+    p.get_children_mut()
+        .iter_mut()
+        .find_map(|child| find_project_mut(child, path))
+}
+
+/// Returns the direct child of `p` with the given name. If there is no
+/// such child, a new one is created in a sub-directory named after it.
+fn child_project(p: &mut Project, name: String) -> Result<&mut Project> {
+    if let Some(i) = p.get_children().iter().position(|c| c.data.name == name) {
+        return Ok(&mut p.get_children_mut()[i]);
+    }
+
+    let dir_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let dir = p
+        .path()
+        .parent()
+        .context("project file has no parent directory")?
+        .join(dir_name);
+    // created once the project is saved
+
+    let project_file = dir.join(tutel::PROJECT_FILE_NAME);
+    let child = if project_file.is_file() {
+        let child = Project::load(project_file, p.steps() + 1)?;
+        if !child.is_child() {
+            bail!("{} is not a child project", dir.to_string_lossy());
+        }
+        child
+    } else {
+        Project::new(project_file, p.steps() + 1, name, true)
+    };
+
+    p.attach_child(child);
+
+    // just attached, cannot be empty
+    Ok(p.get_children_mut().last_mut().unwrap())
+}
+
+/// Registers `tutel merge-driver` for all project files in the current
+/// git repository
+fn install_merge_driver() -> Result<()> {
+    let git = |args: &[&str]| -> Result<String> {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .output()
+            .context("unable to run git")?;
+
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?);
+
+    git(&[
+        "config",
+        "merge.tutel.name",
+        "tutel project file merge driver",
+    ])?;
+    git(&[
+        "config",
+        "merge.tutel.driver",
+        "tutel merge-driver %O %A %B",
+    ])?;
+
+    let attributes_file = root.join(".gitattributes");
+    let mut attributes = if attributes_file.is_file() {
+        fs::read_to_string(&attributes_file).context("unable to read .gitattributes")?
+    } else {
+        String::new()
+    };
+
+    let line = format!("{} merge=tutel", tutel::PROJECT_FILE_NAME);
+    if !attributes.lines().any(|l| l.trim() == line) {
+        if !attributes.is_empty() && !attributes.ends_with('\n') {
+            attributes.push('\n');
+        }
+        attributes.push_str(&line);
+        attributes.push('\n');
+
+        fs::write(&attributes_file, attributes).context("unable to write .gitattributes")?;
+    }
+
+    println!(
+        "installed merge driver, remember to commit {}",
+        attributes_file.to_string_lossy()
+    );
+
+    Ok(())
+}
+
 /// Creates a new project
 ///
 /// If no project name is given, the name of the current directory is chosen
-fn new_project(name: Option<String>, force: bool) -> Result<()> {
+fn new_project(name: Option<String>, force: bool, confirm: Confirm) -> Result<()> {
     let path = std::env::current_dir()?;
 
     // TODO: un-hack me
@@ -151,61 +1347,332 @@ fn new_project(name: Option<String>, force: bool) -> Result<()> {
         );
     }
 
-    tutel::new_project(name)?;
+    let is_child = path.ancestors().skip(1).any(tutel::has_project);
+    if confirm.dry_run {
+        let kind = if is_child { "child project" } else { "project" };
+        println!("create {kind} {name} in {}", path.to_string_lossy());
+        return Ok(());
+    }
+
+    // --force replaces all tasks of the existing project
+    if new.exists() && !confirm.yes && io::stdin().is_terminal() {
+        ask(&format!(
+            "replace the project in {} with an empty one?",
+            path.to_string_lossy()
+        ))?;
+    }
+    tutel::new_project(name, is_child)?.save()?;
+
+    Ok(())
+}
+
+/// Saves `p` and its children, with `--dry-run` only prints what would change
+fn save(p: &mut Project, confirm: Confirm, theme: &Theme) -> Result<()> {
+    if proceed(p, confirm, false, theme)? {
+        p.save()?;
+    }
 
     Ok(())
 }
 
-fn stringify_project(project: &Project) -> String {
+/// Whether the changes to `p` and its children should be saved. With
+/// `--dry-run` they are printed instead. Bulk changes are listed and have to
+/// be confirmed first when stdin is a terminal, unless `--yes` was given.
+fn proceed(p: &Project, confirm: Confirm, bulk: bool, theme: &Theme) -> Result<bool> {
+    let mut changes = Vec::new();
+    if confirm.dry_run || (bulk && !confirm.yes && io::stdin().is_terminal()) {
+        stringify_changes(p, theme, &mut changes);
+    }
+
+    if confirm.dry_run {
+        if changes.is_empty() {
+            println!("nothing would change");
+        }
+        for change in changes {
+            println!("{change}");
+        }
+        return Ok(false);
+    }
+
+    if !changes.is_empty() {
+        for change in &changes {
+            eprintln!("{change}");
+        }
+        ask(&format!("apply {} changes?", changes.len()))?;
+    }
+
+    Ok(true)
+}
+
+/// Asks a yes or no question on stderr, anything but yes is an error
+fn ask(question: &str) -> Result<()> {
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("unable to read the answer")?;
+
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => bail!("aborted, nothing was changed"),
+    }
+}
+
+/// One line per unsaved change of `p` and its children
+fn stringify_changes(p: &Project, theme: &Theme, lines: &mut Vec<String>) {
+    let changes = p.unsaved_changes();
+    if changes.is_empty() && p.is_modified() {
+        lines.push(format!(
+            "{} {}",
+            theme.bold(&format!("{:<8}", "reorder")),
+            p.data.name
+        ));
+    }
+
+    for (change, task) in changes {
+        lines.push(format!(
+            "{} {}:{:03} {} {}{}",
+            theme.bold(&format!("{:<8}", change.change())),
+            p.data.name,
+            task.index,
+            theme.bold(&theme.glyphs.separator),
+            theme.marker(task.completed),
+            task.desc
+        ));
+    }
+
+    for child in p.get_children() {
+        stringify_changes(child, theme, lines);
+    }
+}
+
+fn stringify_project(project: &Project, options: &ShowOptions, theme: &Theme) -> String {
     let mut result = String::new();
     let mut tasks = String::new();
     let mut completed = true;
 
     for t in &project.data.tasks {
         tasks.push('\n');
-        tasks.push_str(stringify_task(t).as_str());
+        tasks.push_str(stringify_task(t, options, theme).as_str());
         if !t.completed {
             completed = false;
         }
     }
 
-    let steps = if project.steps == 0 {
+    let steps = if project.steps() == 0 {
         String::new()
     } else {
-        format!(" [-{}]", project.steps).blue().bold().to_string()
+        theme.steps(&format!(" [-{}]", project.steps()))
     };
 
     let marker = if completed {
-        "✓".green().to_string()
+        theme.done(&theme.glyphs.done)
     } else {
-        "X".red().to_string()
+        theme.open(&theme.glyphs.open)
+    };
+
+    let rollup = tutel::estimate::rollup(project);
+    let effort = if rollup.total.is_empty() {
+        String::new()
+    } else {
+        theme.dim(&format!(" {}", stringify_rollup(rollup)))
     };
 
     let headline = format!(
-        "{}{}{}{} {}",
-        '['.yellow().bold(),
+        "{}{}{}{} {}{}",
+        theme.bracket("["),
         marker,
-        ']'.yellow().bold(),
+        theme.bracket("]"),
         steps,
-        project.data.name.bold()
+        theme.bold(&project.data.name),
+        effort
     );
     result.push_str(headline.as_str());
 
     if tasks.is_empty() {
-        result.push_str("\n[empty]");
+        result.push('\n');
+        result.push_str(&theme.glyphs.empty);
     } else {
         result.push_str(tasks.as_str());
     }
 
+    for marker in &project.data.code {
+        result.push('\n');
+        result.push_str(stringify_code_marker(marker, theme).as_str());
+    }
+
+    for child in project.get_children() {
+        result.push_str("\n\n");
+        result.push_str(stringify_project(child, options, theme).as_str());
+    }
+
     result
 }
 
-fn stringify_task(task: &Task) -> String {
-    let marker = if task.completed {
-        "[✓]".green().to_string()
+/// E.g. `4h of 6h30m, 3pts of 5pts left`
+fn stringify_rollup(rollup: Rollup) -> String {
+    let mut parts = Vec::new();
+    if rollup.total.minutes > 0 {
+        parts.push(format!(
+            "{} of {}",
+            Estimate::Minutes(rollup.remaining.minutes),
+            Estimate::Minutes(rollup.total.minutes)
+        ));
+    }
+    if rollup.total.points > 0 {
+        parts.push(format!(
+            "{} of {}",
+            Estimate::Points(rollup.remaining.points),
+            Estimate::Points(rollup.total.points)
+        ));
+    }
+
+    format!("{} left", parts.join(", "))
+}
+
+fn stringify_code_marker(marker: &CodeMarker, theme: &Theme) -> String {
+    format!(
+        "{} {} {} {}",
+        theme.open(&format!("{:<5}", marker.kind)),
+        theme.bold(&theme.glyphs.separator),
+        marker.text,
+        theme.dim(&format!("{}:{}", marker.file, marker.line))
+    )
+}
+
+fn stringify_stats(stats: &Stats, theme: &Theme) -> String {
+    let mut result = String::new();
+
+    let mut rows = Vec::new();
+    stats_rows(&stats.project, 0, &mut rows);
+    let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    for (name, counts) in &rows {
+        result.push_str(&format!(
+            "{} {}\n",
+            theme.bold(&format!("{name:<name_width$}")),
+            stringify_counts(counts, theme)
+        ));
+    }
+    if !stats.project.children.is_empty() {
+        result.push_str(&format!(
+            "{} {}\n",
+            theme.bold(&format!("{:<name_width$}", "total")),
+            stringify_counts(&stats.project.total, theme)
+        ));
+    }
+
+    result.push_str(&format!(
+        "\n{}\n",
+        theme.bold("week        added  completed")
+    ));
+    for week in &stats.weeks {
+        result.push_str(&format!(
+            "{}  {:>5}  {:>9}\n",
+            week.start, week.added, week.completed
+        ));
+    }
+
+    let median = stats
+        .median_time_to_complete
+        .map_or_else(|| String::from("-"), stringify_duration);
+    result.push_str(&format!(
+        "\n{} {}\n",
+        theme.bold("median time to complete:"),
+        median
+    ));
+
+    let chart = tutel::stats::burndown_chart(&stats.burndown, 8);
+    if !chart.is_empty() {
+        result.push_str(&format!(
+            "\n{}\n{}",
+            theme.bold(&format!(
+                "open tasks, last {} days",
+                tutel::stats::BURNDOWN_DAYS
+            )),
+            chart
+        ));
+    }
+
+    result
+}
+
+/// Flattens the project tree into indented names and their own counts
+fn stats_rows(stats: &ProjectStats, depth: usize, rows: &mut Vec<(String, Counts)>) {
+    rows.push((format!("{}{}", "  ".repeat(depth), stats.name), stats.own));
+    for child in &stats.children {
+        stats_rows(child, depth + 1, rows);
+    }
+}
+
+fn stringify_counts(counts: &Counts, theme: &Theme) -> String {
+    let percent = counts
+        .percent_done()
+        .map_or_else(|| String::from("-"), |p| format!("{p:.0}%"));
+
+    format!(
+        "{} open {} done {:>4} archived {:>5}",
+        theme.open(&format!("{:>4}", counts.open)),
+        theme.done(&format!("{:>4}", counts.done)),
+        counts.archived,
+        percent
+    )
+}
+
+fn stringify_duration(duration: Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+fn stringify_archived(entry: &ArchivedTask, theme: &Theme) -> String {
+    format!(
+        "{} {} {} {}{} {}",
+        theme.dim(&entry.archived_at.format("%Y-%m-%d").to_string()),
+        entry.task.id.chars().take(8).collect::<String>(),
+        theme.bold(&theme.glyphs.separator),
+        theme.marker(entry.task.completed),
+        entry.task.desc,
+        theme.dim(&format!("({})", entry.project_name))
+    )
+}
+
+fn stringify_task(task: &Task, options: &ShowOptions, theme: &Theme) -> String {
+    let id = if options.ids {
+        format!(" {}", task.id.chars().take(8).collect::<String>())
     } else {
-        "[X]".red().to_string()
+        String::new()
     };
 
-    format!("{:03} {} {}{}", task.index, "│".bold(), marker, task.desc)
+    let tracked = if task.time.is_empty() {
+        String::new()
+    } else {
+        theme.dim(&format!(" {}", stringify_duration(task.tracked())))
+    };
+
+    let estimate = task
+        .estimate
+        .map(|e| theme.dim(&format!(" ~{e}")))
+        .unwrap_or_default();
+
+    format!(
+        "{:03}{} {} {}{}{}{}",
+        task.index,
+        id,
+        theme.bold(&theme.glyphs.separator),
+        theme.marker(task.completed),
+        task.desc,
+        tracked,
+        estimate
+    )
 }
@@ -0,0 +1,265 @@
+//! Conversion between projects and GitHub-style markdown checklists.
+
+use std::fmt::Write;
+
+use crate::Project;
+
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// A checklist item read from markdown
+#[derive(Debug, PartialEq, Eq)]
+pub struct Item {
+    pub desc: String,
+    pub completed: bool,
+}
+
+/// All items following a heading (or the start of the document)
+#[derive(Debug, PartialEq, Eq)]
+pub struct Section {
+    pub heading: Option<String>,
+    pub items: Vec<Item>,
+}
+
+/// Renders a project and all of its children as markdown. Every project
+/// becomes a heading, nested one level deeper than its parent, followed by
+/// its tasks as a checklist.
+///
+/// If `indices` is set, every item is prefixed with the task index in bold.
+/// [`parse`] strips these prefixes again.
+#[must_use]
+pub fn render(project: &Project, indices: bool) -> String {
+    let mut result = String::new();
+    render_impl(project, indices, 1, &mut result);
+    result
+}
+
+fn render_impl(project: &Project, indices: bool, level: usize, out: &mut String) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    out.push_str(&"#".repeat(level.min(MAX_HEADING_LEVEL)));
+    out.push(' ');
+    out.push_str(&project.data.name);
+    out.push_str("\n\n");
+
+    for task in &project.data.tasks {
+        let marker = if task.completed { 'x' } else { ' ' };
+        // writing to a String cannot fail
+        let _ = write!(out, "- [{marker}] ");
+        if indices {
+            let _ = write!(out, "**{:03}** ", task.index);
+        }
+        out.push_str(&task.desc);
+        out.push('\n');
+    }
+
+    for child in project.get_children() {
+        render_impl(child, indices, level + 1, out);
+    }
+}
+
+/// Parses a markdown checklist. Items may use `-`, `*`, `+` or ordered list
+/// markers and may be nested, nesting is flattened. Lines that are neither
+/// headings nor checklist items are ignored.
+#[must_use]
+pub fn parse(input: &str) -> Vec<Section> {
+    let mut sections = vec![Section {
+        heading: None,
+        items: Vec::new(),
+    }];
+
+    for line in input.lines() {
+        let line = line.trim();
+
+        if let Some(heading) = parse_heading(line) {
+            sections.push(Section {
+                heading: Some(heading.to_string()),
+                items: Vec::new(),
+            });
+        } else if let Some(item) = parse_item(line) {
+            if let Some(section) = sections.last_mut() {
+                section.items.push(item);
+            }
+        }
+    }
+
+    // drop the leading section if nothing came before the first heading
+    if sections.len() > 1 && sections[0].items.is_empty() {
+        sections.remove(0);
+    }
+
+    sections
+}
+
+fn parse_heading(line: &str) -> Option<&str> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > MAX_HEADING_LEVEL {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let heading = rest.trim().trim_end_matches('#').trim_end();
+    if heading.is_empty() {
+        None
+    } else {
+        Some(heading)
+    }
+}
+
+fn parse_item(line: &str) -> Option<Item> {
+    let rest = strip_list_marker(line)?;
+
+    let (completed, rest) = if let Some(rest) = rest.strip_prefix("[ ]") {
+        (false, rest)
+    } else if let Some(rest) = rest
+        .strip_prefix("[x]")
+        .or_else(|| rest.strip_prefix("[X]"))
+    {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let desc = strip_index(rest.trim());
+    if desc.is_empty() {
+        return None;
+    }
+
+    Some(Item {
+        desc: desc.to_string(),
+        completed,
+    })
+}
+
+/// Strips `- `, `* `, `+ `, `1. ` or `1) ` from the start of `line`
+fn strip_list_marker(line: &str) -> Option<&str> {
+    if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        return Some(rest.trim_start());
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(str::trim_start)
+}
+
+/// Strips an index prefix as written by [`render`]
+fn strip_index(desc: &str) -> &str {
+    let Some(rest) = desc.strip_prefix("**") else {
+        return desc;
+    };
+
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    match rest[digits..].strip_prefix("**") {
+        Some(rest) if digits > 0 => rest.trim_start(),
+        _ => desc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{parse, render, Item, Section};
+    use crate::Project;
+
+    fn item(desc: &str, completed: bool) -> Item {
+        Item {
+            desc: desc.to_string(),
+            completed,
+        }
+    }
+
+    #[test]
+    fn render_tree() {
+        let mut root = Project::new(PathBuf::new(), 0, String::from("root"), false);
+        let mut child = Project::new(PathBuf::new(), 1, String::from("child"), true);
+
+        root.add("first".to_string(), false).unwrap();
+        root.add("second".to_string(), true).unwrap();
+        child.add("third".to_string(), false).unwrap();
+        root.attach_child(child);
+
+        assert_eq!(
+            render(&root, false),
+            "# root\n\n- [ ] first\n- [x] second\n\n## child\n\n- [ ] third\n"
+        );
+        assert_eq!(
+            render(&root, true),
+            "# root\n\n- [ ] **000** first\n- [x] **001** second\n\n## child\n\n- [ ] **000** third\n"
+        );
+    }
+
+    #[test]
+    fn parse_checklist() {
+        let input = "
+            some prose that is ignored
+            - [ ] loose item
+
+            # Release
+            - [x] write changelog
+            * [ ] **012** bump version
+              + [X] nested
+            1. [ ] ordered
+            - [] not an item
+            - [ ]
+            -[ ] also not an item
+
+            ## Docs ##
+            - [ ] readme
+            ";
+
+        assert_eq!(
+            parse(input),
+            vec![
+                Section {
+                    heading: None,
+                    items: vec![item("loose item", false)],
+                },
+                Section {
+                    heading: Some(String::from("Release")),
+                    items: vec![
+                        item("write changelog", true),
+                        item("bump version", false),
+                        item("nested", true),
+                        item("ordered", false),
+                    ],
+                },
+                Section {
+                    heading: Some(String::from("Docs")),
+                    items: vec![item("readme", false)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_without_leading_items() {
+        assert_eq!(
+            parse("# only\n- [ ] a"),
+            vec![Section {
+                heading: Some(String::from("only")),
+                items: vec![item("a", false)],
+            }]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Three-way merging of project files, used as a git merge driver.
+
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::{
+    error::{Error, Result},
+    project::{new_id, ProjectData},
+    Task,
+};
+
+/// Merges the project files `base`, `ours` and `theirs` and writes the
+/// result to `ours`, just like git expects a merge driver to.
+///
+/// # Errors
+/// This function will return an error if any of the files could not be
+/// read or parsed, or if the result could not be written.
+pub fn merge_files(base: &Path, ours: &Path, theirs: &Path) -> Result<()> {
+    let read = |path: &Path| -> Result<ProjectData> {
+        let content = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        toml::from_str(&content).map_err(|e| Error::parse(path, &e))
+    };
+
+    // a file added on both branches has an empty base
+    let base_data = if fs::metadata(base).map_or(true, |m| m.len() == 0) {
+        read(ours).map(|mut data| {
+            data.tasks.clear();
+            data
+        })?
+    } else {
+        read(base)?
+    };
+
+    let merged = merge(&base_data, &read(ours)?, &read(theirs)?);
+    let serialized = toml::to_string_pretty(&merged)?;
+    fs::write(ours, serialized).map_err(|e| Error::io(ours, e))?;
+
+    Ok(())
+}
+
+/// Performs a three-way merge of two versions of a project that have
+/// `base` as their common ancestor.
+///
+/// Tasks are matched by id. Tasks added on either side are kept, tasks added
+/// by `theirs` get a new index if it is already taken. Every field of a task
+/// is taken from whichever side changed it, `ours` wins if both did. A task
+/// removed on one side is only removed if the other side did not change it.
+#[must_use]
+pub fn merge(base: &ProjectData, ours: &ProjectData, theirs: &ProjectData) -> ProjectData {
+    let mut tasks: Vec<Task> = Vec::new();
+
+    for our in &ours.tasks {
+        let key = task_key(our);
+        let base_task = find(base, &key);
+        let their_task = find(theirs, &key);
+
+        match (base_task, their_task) {
+            (Some(base_task), Some(their_task)) => {
+                tasks.push(merge_task(base_task, our, their_task));
+            }
+            // removed by them, keep it only if we changed it
+            (Some(base_task), None) => {
+                if !same_content(base_task, our) {
+                    tasks.push(our.clone());
+                }
+            }
+            // added by us
+            (None, Some(their_task)) => {
+                let mut task = our.clone();
+                if their_task.completed && !our.completed {
+                    task.completed = true;
+                    task.completed_at = their_task.completed_at;
+                }
+                tasks.push(task);
+            }
+            (None, None) => tasks.push(our.clone()),
+        }
+    }
+
+    let mut used: HashSet<usize> = tasks.iter().map(|t| t.index).collect();
+
+    for their in &theirs.tasks {
+        let key = task_key(their);
+        if find(ours, &key).is_some() {
+            continue;
+        }
+
+        // removed by us, keep it only if they changed it
+        if let Some(base_task) = find(base, &key) {
+            if same_content(base_task, their) {
+                continue;
+            }
+        }
+
+        let mut task = their.clone();
+        if used.contains(&task.index) {
+            task.index = free_index(&used);
+        }
+        used.insert(task.index);
+        tasks.push(task);
+    }
+
+    for task in tasks.iter_mut().filter(|t| t.id.is_empty()) {
+        task.id = new_id();
+    }
+
+    ProjectData {
+        name: pick(&base.name, &ours.name, &theirs.name).clone(),
+        tasks,
+        is_child: *pick(&base.is_child, &ours.is_child, &theirs.is_child),
+        // generated from the code, which git merges on its own
+        code: ours.code.clone(),
+        hooks: pick(&base.hooks, &ours.hooks, &theirs.hooks).clone(),
+    }
+}
+
+/// Identifies a task across versions. Tasks saved before ids existed fall
+/// back to their index.
+fn task_key(task: &Task) -> String {
+    if task.id.is_empty() {
+        format!("index:{}", task.index)
+    } else {
+        task.id.clone()
+    }
+}
+
+fn find<'a>(data: &'a ProjectData, key: &str) -> Option<&'a Task> {
+    data.tasks.iter().find(|t| task_key(t) == key)
+}
+
+fn same_content(a: &Task, b: &Task) -> bool {
+    a.desc == b.desc
+        && a.completed == b.completed
+        && a.due == b.due
+        && a.tags == b.tags
+        && a.priority == b.priority
+        && a.estimate == b.estimate
+        && a.time == b.time
+        && a.index == b.index
+}
+
+/// Takes `theirs` if only they changed the value, `ours` otherwise
+fn pick<'a, T: PartialEq>(base: &T, ours: &'a T, theirs: &'a T) -> &'a T {
+    if ours == base {
+        theirs
+    } else {
+        ours
+    }
+}
+
+fn merge_task(base: &Task, ours: &Task, theirs: &Task) -> Task {
+    let mut task = ours.clone();
+
+    task.desc
+        .clone_from(pick(&base.desc, &ours.desc, &theirs.desc));
+    task.due = *pick(&base.due, &ours.due, &theirs.due);
+    task.tags
+        .clone_from(pick(&base.tags, &ours.tags, &theirs.tags));
+    task.priority = *pick(&base.priority, &ours.priority, &theirs.priority);
+    task.estimate = *pick(&base.estimate, &ours.estimate, &theirs.estimate);
+
+    if ours.completed == base.completed {
+        task.completed = theirs.completed;
+        task.completed_at = theirs.completed_at;
+    }
+
+    // intervals are kept unless one side removed them, so time tracked on
+    // both sides adds up
+    let mut time: Vec<_> = ours
+        .time
+        .iter()
+        .filter(|i| theirs.time.contains(i) || !base.time.contains(i))
+        .copied()
+        .collect();
+    time.extend(
+        theirs
+            .time
+            .iter()
+            .filter(|i| !base.time.contains(i) && !ours.time.contains(i)),
+    );
+    time.sort();
+    task.time = time;
+
+    task
+}
+
+/// The lowest index not in use
+fn free_index(used: &HashSet<usize>) -> usize {
+    // at least one of these is always free
+    (0..=used.len())
+        .find(|i| !used.contains(i))
+        .unwrap_or(used.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::merge;
+    use crate::{hooks::Hooks, timer::Interval, ProjectData, Task};
+
+    fn data(tasks: Vec<Task>) -> ProjectData {
+        ProjectData {
+            name: String::from("merge"),
+            tasks,
+            is_child: false,
+            code: Vec::new(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    fn task(desc: &str, completed: bool, index: usize, id: &str) -> Task {
+        Task::with_id(desc, completed, index, id)
+    }
+
+    #[test]
+    fn union_of_added_tasks() {
+        let base = data(vec![task("a", false, 0, "id-a")]);
+        let ours = data(vec![
+            task("a", false, 0, "id-a"),
+            task("b", false, 1, "id-b"),
+        ]);
+        let theirs = data(vec![
+            task("a", false, 0, "id-a"),
+            task("c", false, 1, "id-c"),
+        ]);
+
+        assert_eq!(
+            merge(&base, &ours, &theirs).tasks,
+            vec![
+                task("a", false, 0, "id-a"),
+                task("b", false, 1, "id-b"),
+                task("c", false, 2, "id-c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn take_changed_fields() {
+        let base = data(vec![
+            task("a", false, 0, "id-a"),
+            task("b", false, 1, "id-b"),
+        ]);
+        let ours = data(vec![
+            task("a", true, 0, "id-a"),
+            task("b", false, 1, "id-b"),
+        ]);
+        let theirs = data(vec![
+            task("renamed", false, 0, "id-a"),
+            task("b", true, 1, "id-b"),
+        ]);
+
+        assert_eq!(
+            merge(&base, &ours, &theirs).tasks,
+            vec![task("renamed", true, 0, "id-a"), task("b", true, 1, "id-b")]
+        );
+    }
+
+    #[test]
+    fn removals() {
+        let base = data(vec![
+            task("a", false, 0, "id-a"),
+            task("b", false, 1, "id-b"),
+            task("c", false, 2, "id-c"),
+        ]);
+        // we removed a and changed c
+        let ours = data(vec![
+            task("b", false, 1, "id-b"),
+            task("c", true, 2, "id-c"),
+        ]);
+        // they removed b and c
+        let theirs = data(vec![task("a", false, 0, "id-a")]);
+
+        assert_eq!(
+            merge(&base, &ours, &theirs).tasks,
+            vec![task("c", true, 2, "id-c")]
+        );
+    }
+
+    #[test]
+    fn tracked_time() {
+        let interval = |hour| Interval {
+            start: Utc.with_ymd_and_hms(2026, 10, 18, hour, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2026, 10, 18, hour, 30, 0).unwrap(),
+        };
+        let with_time = |hours: &[u32]| {
+            let mut task = task("a", false, 0, "id-a");
+            task.time = hours.iter().map(|h| interval(*h)).collect();
+            data(vec![task])
+        };
+
+        // we tracked 12, they tracked 11 and removed 9
+        let base = with_time(&[9, 10]);
+        let ours = with_time(&[9, 10, 12]);
+        let theirs = with_time(&[10, 11]);
+
+        assert_eq!(
+            merge(&base, &ours, &theirs).tasks,
+            with_time(&[10, 11, 12]).tasks
+        );
+    }
+
+    #[test]
+    fn renamed_project() {
+        let base = data(Vec::new());
+        let ours = data(Vec::new());
+        let mut theirs = data(Vec::new());
+        theirs.name = String::from("new name");
+
+        assert_eq!(merge(&base, &ours, &theirs).name, "new name");
+    }
+}
@@ -1,23 +1,69 @@
-use anyhow::Context;
-use anyhow::{bail, Result};
-use std::fs;
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::hooks::{self, Event, Hooks};
+use crate::scan::CodeMarker;
+use crate::storage::{FileStorage, Revision, Storage};
+use crate::{
+    estimate::{by_importance, Estimate},
+    timer::Interval,
+};
+
+/// The highest index a task can have, indices are shown with three digits
+pub const MAX_INDEX: usize = 999;
 
 /// A Project holds multiple tasks. It also holds the location of
 /// the file these tasks were loaded from and how many
 /// recursive steps have been taken to reach that file.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub struct Project {
     pub data: ProjectData,
     path: PathBuf,
     steps: isize,
-    children: Vec<Project>,
+    children: Vec<Self>,
+    storage: Arc<dyn Storage>,
+    /// Revision when the project was loaded or last saved. Used to detect
+    /// changes made by someone else in the meantime.
+    revision: Option<Revision>,
+    /// Tasks as they were loaded or last saved, to tell hooks what changed
+    saved: Vec<Task>,
+    hooks: Arc<hooks::Settings>,
+}
+
+impl PartialEq for Project {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+            && self.path == other.path
+            && self.steps == other.steps
+            && self.children == other.children
+    }
 }
 
+impl Eq for Project {}
+
 impl Project {
-    /// Creates a new project with no tasks and no children
+    /// Creates a new project with no tasks and no children, which will be
+    /// saved to the file system
     #[must_use]
-    pub const fn new(project_file: PathBuf, steps: isize, name: String, is_child: bool) -> Self {
+    pub fn new(project_file: PathBuf, steps: isize, name: String, is_child: bool) -> Self {
+        Self::with_storage(Arc::new(FileStorage), project_file, steps, name, is_child)
+    }
+
+    /// Creates a new project with no tasks and no children, which will be
+    /// saved to `storage`
+    #[must_use]
+    pub fn with_storage(
+        storage: Arc<dyn Storage>,
+        project_file: PathBuf,
+        steps: isize,
+        name: String,
+        is_child: bool,
+    ) -> Self {
         Self {
             path: project_file,
             steps,
@@ -26,7 +72,13 @@ impl Project {
                 name,
                 tasks: Vec::new(),
                 is_child,
+                code: Vec::new(),
+                hooks: Hooks::default(),
             },
+            storage,
+            revision: None,
+            saved: Vec::new(),
+            hooks: Arc::default(),
         }
     }
 
@@ -36,21 +88,46 @@ impl Project {
     /// This function will return an Error when the file doesn't exists, or
     /// a Project couldn't be loaded from it.
     pub fn load(project_file: PathBuf, steps: isize) -> Result<Self> {
-        if !project_file.is_file() {
-            bail!("not a project file: {}", project_file.to_string_lossy())
-        }
+        Self::load_from(Arc::new(FileStorage), project_file, steps)
+    }
 
-        let file_content =
-            fs::read_to_string(project_file.as_path()).context("unable to read project file")?;
+    /// Tries to load a project from `storage`.
+    ///
+    /// # Errors
+    /// This function will return an Error when there is no project at
+    /// `project_file`, or a Project couldn't be loaded from it.
+    pub fn load_from(
+        storage: Arc<dyn Storage>,
+        project_file: PathBuf,
+        steps: isize,
+    ) -> Result<Self> {
+        let (mut data, revision) = storage.read(&project_file)?;
+
+        let mut indices = HashSet::new();
+        for task in &data.tasks {
+            if !indices.insert(task.index) {
+                return Err(Error::DuplicateIndex {
+                    path: project_file,
+                    index: task.index,
+                });
+            }
+        }
 
-        let data: ProjectData =
-            toml::from_str(file_content.as_str()).context("invalid project file syntax")?;
+        // loading never writes, so tasks without an id get one derived from
+        // where they are. It stays the same on every load until it is saved.
+        for task in data.tasks.iter_mut().filter(|t| t.id.is_empty()) {
+            task.id = derived_id(&project_file, task.index);
+        }
 
         Ok(Self {
             path: project_file,
             children: Vec::new(),
+            saved: data.tasks.clone(),
             data,
             steps,
+            storage,
+            revision: Some(revision),
+            hooks: Arc::default(),
         })
     }
 
@@ -59,16 +136,107 @@ impl Project {
     /// # Errors
     /// This function will return an Error when the file this project was
     /// loaded from can't be written(doesnt exist, permission denied) or the
-    /// project could not be serialized. Both of these are not very likely to occur
+    /// project could not be serialized. Both of these are not very likely to occur.
+    /// If the file of this project or of any child was changed since it was
+    /// loaded, [`Error::LockConflict`] is returned and nothing is written. The
+    /// same goes for a `pre-save` hook rejecting one of the changes anywhere
+    /// in the tree, which results in [`Error::HookRejected`].
     pub fn save(&mut self) -> Result<()> {
-        let serialized = toml::to_string_pretty(&self.data)?;
-        fs::write(self.path.as_path(), serialized).context("unable to write project file")?;
+        // nothing is written before the whole tree is known to be saveable
+        self.check_save()?;
+        self.write()
+    }
+
+    /// Checks the revision and runs the `pre-save` hooks of this project and
+    /// all of its children
+    fn check_save(&self) -> Result<()> {
+        if let Some(expected) = self.revision {
+            if self.storage.revision(&self.path) != Some(expected) {
+                return Err(Error::LockConflict(self.path.clone()));
+            }
+        }
+
+        for (change, task) in &hooks::changes(&self.saved, &self.data.tasks) {
+            for command in self.hook_commands(Event::PreSave) {
+                if !hooks::run(
+                    command,
+                    Event::PreSave,
+                    *change,
+                    &self.data.name,
+                    &self.path,
+                    task,
+                )? {
+                    return Err(Error::HookRejected {
+                        command: command.clone(),
+                        task: format!("{}:{:03}", self.data.name, task.index),
+                    });
+                }
+            }
+        }
+
+        self.children.iter().try_for_each(Self::check_save)
+    }
+
+    /// Writes this project and all of its children, see [`Project::save`]
+    fn write(&mut self) -> Result<()> {
+        let changes = hooks::changes(&self.saved, &self.data.tasks);
+
+        self.revision = Some(self.storage.write(&self.path, &self.data)?);
+        self.saved.clone_from(&self.data.tasks);
+
+        // the change is saved already, failing hooks can't undo it
+        for (change, task) in &changes {
+            for command in self.hook_commands(*change) {
+                let _ = hooks::run(command, *change, *change, &self.data.name, &self.path, task);
+            }
+        }
+
+        self.children.iter_mut().try_for_each(Self::write)
+    }
+
+    /// Whether this project or one of its children was changed in storage
+    /// since it was loaded or last saved
+    #[must_use]
+    pub fn is_stale(&self) -> bool {
+        self.storage.revision(&self.path) != self.revision
+            || self.children.iter().any(Self::is_stale)
+    }
+
+    /// The changes to the tasks of this project, without its children, since
+    /// it was loaded or last saved. Tasks that were only moved are no change,
+    /// see [`Project::is_modified`].
+    #[must_use]
+    pub fn unsaved_changes(&self) -> Vec<(Event, Task)> {
+        hooks::changes(&self.saved, &self.data.tasks)
+    }
+
+    /// Whether the tasks of this project, without its children, or their
+    /// order differ from what was loaded or last saved. A project that was
+    /// never saved is always modified.
+    #[must_use]
+    pub fn is_modified(&self) -> bool {
+        self.revision.is_none() || self.saved != self.data.tasks
+    }
+
+    /// Sets the hooks from the user configuration for this project and all of
+    /// its children
+    pub fn set_hooks(&mut self, settings: &Arc<hooks::Settings>) {
+        self.hooks = settings.clone();
 
         for child in &mut self.children {
-            child.save()?;
+            child.set_hooks(settings);
         }
+    }
 
-        Ok(())
+    /// The commands to run for `event`, the ones from the project file first
+    fn hook_commands(&self, event: Event) -> impl Iterator<Item = &String> {
+        let own = if self.hooks.project_hooks {
+            self.data.hooks.commands(event)
+        } else {
+            &[]
+        };
+
+        own.iter().chain(self.hooks.global.commands(event))
     }
 
     #[must_use]
@@ -76,18 +244,20 @@ impl Project {
         self.data.is_child
     }
 
-    /// Attaches a child to this project
+    /// Attaches a child to this project. The child runs the same hooks from
+    /// the user configuration.
     ///
     /// # Panics
     /// This functions panics in test and debug builds when a child is attached that isnt
     /// actually a child(`is_child = false`).
-    pub fn attach_child(&mut self, child: Self) {
+    pub fn attach_child(&mut self, mut child: Self) {
         assert!(
             child.data.is_child,
             "attached a child to a project that isnt actually a child: {}",
             child.path.to_string_lossy()
         );
 
+        child.set_hooks(&self.hooks);
         self.children.push(child);
     }
 
@@ -103,36 +273,189 @@ impl Project {
             }
         }
 
-        bail!("no task {}:{} found", self.data.name, index)
+        Err(task_not_found(&self.data.name, &index))
     }
 
-    pub fn find_task(&mut self, selector: &str, index: usize) -> Result<&mut Task> {
-        if self.data.name.starts_with(selector) {
-            return self.get_task_mut(index);
-        }
-
-        let mut result = None;
-        if !self.children.is_empty() {
-            for child in &mut self.children {
-                let child_task = child.find_task(selector, index);
+    /// Resolves a reference to a Task of this project or one of its children.
+    /// An index refers to a Task of this project, `project:index` to one of
+    /// the project selected like [`Self::select_mut`] does and an id prefix to
+    /// any Task of the tree. Returns the project containing the Task and its
+    /// index.
+    ///
+    /// # Errors
+    /// This function will return an error if no Task matches, if an id
+    /// prefix matches more than one Task or if the project can't be selected.
+    pub fn resolve(&mut self, task: &TaskRef) -> Result<(&mut Self, usize)> {
+        let (route, index) = self.locate(task)?;
+        Ok((self.follow_mut(&route), index))
+    }
 
-                if child_task.is_ok() {
-                    result = Some(child_task);
+    /// The child indices leading to the project of the referenced Task,
+    /// together with the index of the Task
+    fn locate(&self, task: &TaskRef) -> Result<(Vec<usize>, usize)> {
+        let prefix = match task {
+            TaskRef::Index(index) => {
+                if self.data.tasks.iter().any(|t| t.index == *index) {
+                    return Ok((Vec::new(), *index));
                 }
+                return Err(task_not_found(&self.data.name, index));
             }
+            TaskRef::InProject(selector, index) => {
+                let route = self.select_route(selector)?;
+                let project = route.iter().fold(self, |project, &i| &project.children[i]);
+                if project.data.tasks.iter().any(|t| t.index == *index) {
+                    return Ok((route, *index));
+                }
+                return Err(task_not_found(&project.data.name, index));
+            }
+            TaskRef::Id(prefix) => prefix,
+        };
+
+        let mut candidates = Vec::new();
+        self.collect_ids(prefix, &mut Vec::new(), &mut candidates);
+
+        match candidates.as_slice() {
+            [] => Err(task_not_found(&self.data.name, prefix)),
+            [(route, _, task)] => Ok((route.clone(), task.index)),
+            _ => Err(Error::AmbiguousTask {
+                prefix: prefix.clone(),
+                candidates: candidates
+                    .iter()
+                    .map(|(_, name, t)| format!("{name}:{:03} ({})", t.index, t.id))
+                    .collect(),
+            }),
         }
+    }
 
-        if let Some(result) = result {
-            return result;
+    /// Collects the Tasks of the tree whose id starts with `prefix`, together
+    /// with the child indices leading to their project and its name
+    fn collect_ids<'a>(
+        &'a self,
+        prefix: &str,
+        route: &mut Vec<usize>,
+        found: &mut Vec<(Vec<usize>, &'a str, &'a Task)>,
+    ) {
+        for task in self.data.tasks.iter().filter(|t| t.id_starts_with(prefix)) {
+            found.push((route.clone(), &self.data.name, task));
         }
 
-        bail!("no task {}:{} found", self.data.name, index)
+        for (i, child) in self.children.iter().enumerate() {
+            route.push(i);
+            child.collect_ids(prefix, route, found);
+            route.pop();
+        }
     }
 
-    pub fn add(&mut self, name: String, completed: bool) {
-        self.data
-            .tasks
-            .push(Task::new(name, completed, self.next_index()));
+    /// The project the child indices of `route` lead to
+    fn follow_mut(&mut self, route: &[usize]) -> &mut Self {
+        route
+            .iter()
+            .fold(self, |project, &i| &mut project.children[i])
+    }
+
+    /// Searches this project and all of its children for a Task. The project
+    /// is selected by its name (prefix), the Task by its index.
+    ///
+    /// # Errors
+    /// This function will return an error if no matching project or Task
+    /// could be found, or if the name matches more than one project.
+    pub fn find_task(&mut self, selector: &str, index: usize) -> Result<&mut Task> {
+        self.select_mut(selector)?.get_task_mut(index)
+    }
+
+    /// Searches this project and all of its children for a project by its
+    /// name. A prefix of the name is enough, unless another project has that
+    /// exact name.
+    ///
+    /// # Errors
+    /// This function will return an error if no project matches, or if more
+    /// than one does. The error lists the candidates then.
+    pub fn select_mut(&mut self, selector: &str) -> Result<&mut Self> {
+        let route = self.select_route(selector)?;
+        Ok(self.follow_mut(&route))
+    }
+
+    /// The child indices leading to the project selected by `selector`
+    fn select_route(&self, selector: &str) -> Result<Vec<usize>> {
+        let mut found = Vec::new();
+        self.collect_named(selector, &mut Vec::new(), &mut found);
+
+        let exact: Vec<_> = found
+            .iter()
+            .filter(|(_, p)| p.data.name == selector)
+            .cloned()
+            .collect();
+        let candidates = if exact.is_empty() { found } else { exact };
+
+        match candidates.as_slice() {
+            [] => Err(Error::ProjectNotFound(selector.to_owned())),
+            [(route, _)] => Ok(route.clone()),
+            _ => Err(Error::AmbiguousProject {
+                selector: selector.to_owned(),
+                candidates: candidates
+                    .iter()
+                    .map(|(_, p)| format!("{} ({})", p.data.name, p.path.to_string_lossy()))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Collects the projects whose name starts with `selector`, together with
+    /// the child indices leading to them
+    fn collect_named<'a>(
+        &'a self,
+        selector: &str,
+        route: &mut Vec<usize>,
+        found: &mut Vec<(Vec<usize>, &'a Self)>,
+    ) {
+        if self.data.name.starts_with(selector) {
+            found.push((route.clone(), self));
+        }
+
+        for (i, child) in self.children.iter().enumerate() {
+            route.push(i);
+            child.collect_named(selector, route, found);
+            route.pop();
+        }
+    }
+
+    /// Names used by more than one project of the tree, sorted. Such projects
+    /// can't be told apart by their name.
+    #[must_use]
+    pub fn duplicate_names(&self) -> Vec<&str> {
+        let mut found = Vec::new();
+        self.collect_named("", &mut Vec::new(), &mut found);
+
+        let mut names: Vec<_> = found
+            .into_iter()
+            .map(|(_, p)| p.data.name.as_str())
+            .collect();
+        names.sort_unstable();
+
+        let mut duplicates: Vec<_> = names
+            .windows(2)
+            .filter(|pair| pair[0] == pair[1])
+            .map(|pair| pair[0])
+            .collect();
+        duplicates.dedup();
+        duplicates
+    }
+
+    /// Adds a new Task with the next free index.
+    ///
+    /// # Errors
+    /// This function will return an error if all indices are in use.
+    pub fn add(&mut self, name: String, completed: bool) -> Result<&mut Task> {
+        let mut task = Task::new(name, completed, self.next_index()?);
+        task.created_at = Some(now());
+        if completed {
+            task.completed_at = task.created_at;
+        }
+
+        self.data.tasks.push(task);
+
+        let last = self.data.tasks.len() - 1;
+        Ok(&mut self.data.tasks[last])
     }
 
     pub fn remove(&mut self, index: usize) {
@@ -148,8 +471,8 @@ impl Project {
     }
 
     pub fn mark_completion_all(&mut self, completed: bool) {
-        for mut t in &mut self.data.tasks {
-            t.completed = completed;
+        for t in &mut self.data.tasks {
+            t.set_completed(completed);
         }
     }
 
@@ -160,32 +483,79 @@ impl Project {
     /// could not be found.
     pub fn mark_completion(&mut self, index: usize, completed: bool) -> Result<()> {
         let task = self.get_task_mut(index)?;
-        task.completed = completed;
+        task.set_completed(completed);
         Ok(())
     }
 
-    /// Calculates the next highest unused index.
+    /// Moves the Task with the given index to another position in the list.
+    /// Indices are not changed.
     ///
-    /// Wraps around to 0 after 999 is reached.
-    #[must_use]
-    pub fn next_index(&self) -> usize {
-        if self.data.tasks.is_empty() {
-            return 0;
+    /// # Errors
+    /// This function will return an error if the Task to move, or the Task to
+    /// move it before, could not be found.
+    pub fn move_task(&mut self, index: usize, to: Position) -> Result<()> {
+        let from = self.position_of(index)?;
+        if let Position::Before(before) = to {
+            // checked up front, so a missing target doesn't lose the task
+            self.position_of(before)?;
+            if before == index {
+                return Ok(());
+            }
         }
 
-        let mut highest = 0;
-        for t in &self.data.tasks {
-            if t.index > highest {
-                highest = t.index;
-            }
+        let task = self.data.tasks.remove(from);
+        let at = match to {
+            Position::Top => 0,
+            Position::Bottom => self.data.tasks.len(),
+            Position::Before(before) => self.position_of(before)?,
+        };
+        self.data.tasks.insert(at, task);
+
+        Ok(())
+    }
+
+    /// Sorts the Tasks, keeping their indices. Tasks that compare equal keep
+    /// their order.
+    pub fn sort_tasks(&mut self, by: SortKey) {
+        match by {
+            SortKey::Priority => self.data.tasks.sort_by(by_importance),
+            SortKey::Due => self.data.tasks.sort_by_key(|t| (t.due.is_none(), t.due)),
+            SortKey::Desc => self
+                .data
+                .tasks
+                .sort_by_cached_key(|t| t.desc.to_lowercase()),
+            SortKey::Index => self.data.tasks.sort_by_key(|t| t.index),
         }
+    }
 
-        // Wrap around
-        if highest >= 999 {
-            0
-        } else {
-            highest + 1
+    fn position_of(&self, index: usize) -> Result<usize> {
+        self.data
+            .tasks
+            .iter()
+            .position(|t| t.index == index)
+            .ok_or_else(|| task_not_found(&self.data.name, &index))
+    }
+
+    /// Calculates the next highest unused index.
+    ///
+    /// Wraps around to the lowest unused index after 999 is reached.
+    ///
+    /// # Errors
+    /// This function will return an error if all 1000 indices are in use.
+    pub fn next_index(&self) -> Result<usize> {
+        let Some(highest) = self.data.tasks.iter().map(|t| t.index).max() else {
+            return Ok(0);
+        };
+
+        if highest < MAX_INDEX {
+            return Ok(highest + 1);
         }
+
+        // Wrap around
+        let used: HashSet<_> = self.data.tasks.iter().map(|t| t.index).collect();
+        (0..=MAX_INDEX)
+            .find(|i| !used.contains(i))
+            .ok_or_else(|| Error::ProjectFull(self.data.name.clone()))
     }
 
     #[must_use]
@@ -198,46 +568,240 @@ impl Project {
         self.steps
     }
 
+    /// The storage this project is saved to
     #[must_use]
-    pub fn get_children(&self) -> &[Self] {
+    pub const fn storage(&self) -> &Arc<dyn Storage> {
+        &self.storage
+    }
+
+    #[must_use]
+    pub const fn get_children(&self) -> &[Self] {
         self.children.as_slice()
     }
+
+    #[must_use]
+    pub const fn get_children_mut(&mut self) -> &mut [Self] {
+        self.children.as_mut_slice()
+    }
+}
+
+/// Where to move a Task to, see [`Project::move_task`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Top,
+    Bottom,
+    /// Right before the Task with this index
+    Before(usize),
+}
+
+/// What to sort Tasks by, see [`Project::sort_tasks`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Most important first, see [`by_importance`]
+    Priority,
+    /// Earliest first, Tasks without a due date last
+    Due,
+    /// Alphabetically, ignoring case
+    Desc,
+    Index,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "priority" => Ok(Self::Priority),
+            "due" => Ok(Self::Due),
+            "desc" => Ok(Self::Desc),
+            "index" => Ok(Self::Index),
+            _ => Err(format!(
+                "cannot sort by {s}, expected priority, due, desc or index"
+            )),
+        }
+    }
+}
+
+/// Refers to a Task by its index, by the name of its project and its index
+/// or by a prefix of its id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskRef {
+    Index(usize),
+    /// `project:index`, as tutel prints tasks of a tree
+    InProject(String, usize),
+    Id(String),
+}
+
+impl From<usize> for TaskRef {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+impl FromStr for TaskRef {
+    type Err = String;
+
+    /// Up to three digits are an index, optionally preceded by a project name
+    /// and a colon. Anything else is an id prefix, as is everything after a
+    /// leading `@` for ids that look like one of the other two.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(prefix) = s.strip_prefix('@') {
+            if prefix.is_empty() {
+                return Err(String::from("empty id prefix"));
+            }
+            return Ok(Self::Id(prefix.to_owned()));
+        }
+
+        if s.is_empty() {
+            return Err(String::from("empty task reference"));
+        }
+
+        if let Some(index) = parse_index(s) {
+            return Ok(Self::Index(index));
+        }
+
+        if let Some((project, index)) = s.rsplit_once(':') {
+            return match parse_index(index) {
+                Some(index) if !project.is_empty() => {
+                    Ok(Self::InProject(project.to_owned(), index))
+                }
+                _ => Err(format!("{s} is not a task, expected project:index")),
+            };
+        }
+
+        Ok(Self::Id(s.to_owned()))
+    }
+}
+
+/// Up to three digits
+fn parse_index(s: &str) -> Option<usize> {
+    if s.is_empty() || s.len() > 3 || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // cannot overflow with at most 3 digits
+    s.parse().ok()
 }
 
 /// The part of a Project that needs to be saved/loaded
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
 pub struct ProjectData {
     pub name: String,
     pub tasks: Vec<Task>,
     pub is_child: bool,
+    /// Marker comments found by the last scan of the source code
+    pub code: Vec<CodeMarker>,
+    /// Only run if enabled in the user configuration
+    pub hooks: Hooks,
 }
 
 /// A completable Task within a Project
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Task {
     pub desc: String,
     pub index: usize,
     pub completed: bool,
+    /// When the task was added, if known
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the task was completed, if known
+    pub completed_at: Option<DateTime<Utc>>,
+    pub due: Option<NaiveDate>,
+    pub tags: Vec<String>,
+    /// Lower numbers are more important, 1 being the most important
+    pub priority: Option<u8>,
+    /// How much effort the task is expected to take
+    pub estimate: Option<Estimate>,
+    /// Time spent working on the task
+    pub time: Vec<Interval>,
+    /// Globally unique and, unlike the index, never reused. Tasks imported
+    /// from other applications get an id derived from their foreign one (e.g.
+    /// an iCalendar UID).
+    pub id: String,
 }
 
 impl Task {
+    /// Creates a new Task with a freshly generated id
     pub fn new(name: impl Into<String>, completed: bool, index: usize) -> Self {
+        Self::with_id(name, completed, index, new_id())
+    }
+
+    /// Whether the id of this task starts with `prefix`, ignoring ASCII case
+    #[must_use]
+    pub fn id_starts_with(&self, prefix: &str) -> bool {
+        self.id
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    }
+
+    pub fn with_id(
+        name: impl Into<String>,
+        completed: bool,
+        index: usize,
+        id: impl Into<String>,
+    ) -> Self {
         Self {
             desc: name.into(),
             completed,
             index,
+            created_at: None,
+            completed_at: None,
+            due: None,
+            tags: Vec::new(),
+            priority: None,
+            estimate: None,
+            time: Vec::new(),
+            id: id.into(),
+        }
+    }
+
+    /// Marks this task as completed/not completed, keeping track of
+    /// when it was completed.
+    pub fn set_completed(&mut self, completed: bool) {
+        if completed && !self.completed {
+            self.completed_at = Some(now());
+        } else if !completed {
+            self.completed_at = None;
         }
+
+        self.completed = completed;
+    }
+}
+
+fn task_not_found(project: &str, task: &impl ToString) -> Error {
+    Error::TaskNotFound {
+        project: project.to_string(),
+        task: task.to_string(),
     }
 }
 
+/// Generates a new random task id
+#[must_use]
+pub fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// The id of a task that was saved without one, the same for every load
+fn derived_id(project_file: &Path, index: usize) -> String {
+    let name = format!("{}#{index}", project_file.display());
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes()).to_string()
+}
+
+/// The current time, truncated to whole seconds as that is what gets saved.
+#[must_use]
+pub fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(0)
+}
+
 #[cfg(test)]
 mod tests {
+    use chrono::NaiveDate;
     use pretty_assertions::assert_eq;
     use std::{io::Write, path::PathBuf};
     use tempfile::NamedTempFile;
 
-    use super::{Project, Task};
+    use super::{Position, Project, SortKey, Task, TaskRef};
+    use crate::{hooks::Event, Error};
 
     #[test]
     fn load() {
@@ -251,11 +815,13 @@ mod tests {
             desc = 'testtask'
             completed = true
             index = 67
+            id = 'b1f6f5d2-6c7e-4c31-9a9e-3a6fd1c7c0aa'
 
             [[tasks]]
             desc = 'moretest'
             completed = false
             index = 99
+            id = '5e0c0f8a-4ad4-4a4f-a0c4-5b0d8f3c3e11'
                "#
         )
         .expect("unable to write tmpfile");
@@ -267,12 +833,280 @@ mod tests {
         assert_eq!(
             project.data.tasks,
             vec![
-                Task::new("testtask", true, 67),
-                Task::new("moretest", false, 99),
+                Task::with_id("testtask", true, 67, "b1f6f5d2-6c7e-4c31-9a9e-3a6fd1c7c0aa"),
+                Task::with_id(
+                    "moretest",
+                    false,
+                    99,
+                    "5e0c0f8a-4ad4-4a4f-a0c4-5b0d8f3c3e11"
+                ),
             ]
         );
     }
 
+    #[test]
+    fn load_without_ids() {
+        let mut tmpfile = NamedTempFile::new().expect("unable to create tmpfile");
+        write!(
+            tmpfile,
+            r"
+            name = 'testproject'
+
+            [[tasks]]
+            desc = 'a'
+            completed = false
+            index = 0
+
+            [[tasks]]
+            desc = 'b'
+            completed = false
+            index = 1
+               "
+        )
+        .expect("unable to write tmpfile");
+
+        let project =
+            Project::load(tmpfile.path().to_path_buf(), 0).expect("unable to load project");
+
+        let tasks = &project.data.tasks;
+        assert!(!tasks[0].id.is_empty());
+        assert_ne!(tasks[0].id, tasks[1].id);
+
+        let reloaded =
+            Project::load(tmpfile.path().to_path_buf(), 0).expect("unable to load project");
+        assert_eq!(reloaded.data.tasks, project.data.tasks);
+
+        // loading doesn't write the ids, saving does
+        let content = std::fs::read_to_string(tmpfile.path()).unwrap();
+        assert!(!content.contains(&tasks[0].id));
+
+        let mut project = project;
+        project.save().expect("unable to save project");
+        let saved = Project::load(tmpfile.path().to_path_buf(), 0).expect("unable to load project");
+        assert_eq!(saved.data.tasks, reloaded.data.tasks);
+        assert!(std::fs::read_to_string(tmpfile.path())
+            .unwrap()
+            .contains(&reloaded.data.tasks[0].id));
+    }
+
+    #[test]
+    fn load_errors() {
+        let mut tmpfile = NamedTempFile::new().expect("unable to create tmpfile");
+        write!(tmpfile, "name = 'testproject'\ntasks = [\n").expect("unable to write tmpfile");
+
+        let error = Project::load(tmpfile.path().to_path_buf(), 0).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                Error::Parse {
+                    line: Some(_),
+                    column: Some(_),
+                    ..
+                }
+            ),
+            "{:?}",
+            error
+        );
+
+        let mut tmpfile = NamedTempFile::new().expect("unable to create tmpfile");
+        write!(
+            tmpfile,
+            r"
+            name = 'testproject'
+
+            [[tasks]]
+            desc = 'a'
+            completed = false
+            index = 3
+
+            [[tasks]]
+            desc = 'b'
+            completed = false
+            index = 3
+               "
+        )
+        .expect("unable to write tmpfile");
+
+        let error = Project::load(tmpfile.path().to_path_buf(), 0).unwrap_err();
+        assert!(matches!(error, Error::DuplicateIndex { index: 3, .. }));
+
+        let error = Project::load(PathBuf::from("/does/not/exist"), 0).unwrap_err();
+        assert!(matches!(error, Error::NotAProject(_)));
+    }
+
+    #[test]
+    fn lock_conflict() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join(crate::PROJECT_FILE_NAME);
+
+        let mut project = Project::new(path.clone(), 0, String::from("testproject"), false);
+        project.save().expect("unable to save project");
+
+        let mut first = Project::load(path.clone(), 0).expect("unable to load project");
+        let mut second = Project::load(path, 0).expect("unable to load project");
+
+        first.add(String::from("first"), false).unwrap();
+        first.save().expect("unable to save project");
+        // saving again after our own save is fine
+        first.save().expect("unable to save project");
+
+        second.add(String::from("second"), false).unwrap();
+        assert!(matches!(second.save(), Err(Error::LockConflict(_))));
+    }
+
+    #[test]
+    fn conflicting_child_saves_nothing() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join(crate::PROJECT_FILE_NAME);
+        let child_path = tmpdir.path().join("child").join(crate::PROJECT_FILE_NAME);
+        std::fs::create_dir(child_path.parent().unwrap()).unwrap();
+
+        Project::new(path.clone(), 0, String::from("parent"), false)
+            .save()
+            .expect("unable to save project");
+        Project::new(child_path.clone(), 1, String::from("child"), true)
+            .save()
+            .expect("unable to save project");
+
+        let mut project = Project::load(path.clone(), 0).expect("unable to load project");
+        project.attach_child(Project::load(child_path.clone(), 1).expect("unable to load project"));
+
+        let mut other = Project::load(child_path, 1).expect("unable to load project");
+        other.add(String::from("other"), false).unwrap();
+        other.save().expect("unable to save project");
+
+        project.add(String::from("parent"), false).unwrap();
+        assert!(matches!(project.save(), Err(Error::LockConflict(_))));
+
+        let loaded = Project::load(path, 0).expect("unable to load project");
+        assert!(loaded.data.tasks.is_empty());
+    }
+
+    #[test]
+    fn unsaved_changes() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join(crate::PROJECT_FILE_NAME);
+
+        let mut project = Project::new(path.clone(), 0, String::from("testproject"), false);
+        assert!(project.is_modified());
+        project.add(String::from("first"), false).unwrap();
+        project.add(String::from("second"), false).unwrap();
+        project.save().expect("unable to save project");
+        assert!(!project.is_modified());
+        assert_eq!(project.unsaved_changes(), Vec::new());
+
+        project.move_task(1, Position::Top).unwrap();
+        assert!(project.is_modified());
+        assert_eq!(project.unsaved_changes(), Vec::new());
+
+        project.mark_completion(0, true).unwrap();
+        let changes = project.unsaved_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, Event::Complete);
+        assert_eq!(changes[0].1.desc, "first");
+
+        // nothing was written
+        let loaded = Project::load(path, 0).expect("unable to load project");
+        assert!(!loaded.data.tasks[0].completed);
+    }
+
+    #[test]
+    fn resolve_task_ref() {
+        let mut project = Project::new(PathBuf::new(), 0, String::from("dummy"), false);
+        project
+            .data
+            .tasks
+            .push(Task::with_id("a", false, 5, "abc123"));
+        project
+            .data
+            .tasks
+            .push(Task::with_id("b", false, 16, "abd456"));
+
+        let mut child = Project::new(PathBuf::from("child"), 1, String::from("child"), true);
+        child
+            .data
+            .tasks
+            .push(Task::with_id("c", false, 7, "abe789"));
+        child
+            .data
+            .tasks
+            .push(Task::with_id("d", false, 16, "fed000"));
+        child
+            .data
+            .tasks
+            .push(Task::with_id("e", false, 20, "FOREIGN:42"));
+        project.attach_child(child);
+
+        let resolve = |project: &mut Project, task: &TaskRef| {
+            project
+                .resolve(task)
+                .map(|(p, index)| (p.data.name.clone(), index))
+        };
+
+        assert_eq!(
+            resolve(&mut project, &TaskRef::Index(16)).unwrap(),
+            (String::from("dummy"), 16)
+        );
+        assert!(resolve(&mut project, &TaskRef::Index(7)).is_err());
+        assert_eq!(
+            resolve(&mut project, &"abc1".parse().unwrap()).unwrap(),
+            (String::from("dummy"), 5)
+        );
+        assert_eq!(
+            resolve(&mut project, &"ABD".parse().unwrap()).unwrap(),
+            (String::from("dummy"), 16)
+        );
+        assert_eq!(
+            resolve(&mut project, &"abe".parse().unwrap()).unwrap(),
+            (String::from("child"), 7)
+        );
+        assert!(matches!(
+            resolve(&mut project, &TaskRef::Id(String::from("ab"))),
+            Err(Error::AmbiguousTask { candidates, .. }) if candidates.len() == 3
+        ));
+        assert!(matches!(
+            resolve(&mut project, &TaskRef::Id(String::from("ff"))),
+            Err(Error::TaskNotFound { .. })
+        ));
+        assert_eq!(
+            resolve(&mut project, &"ch:016".parse().unwrap()).unwrap(),
+            (String::from("child"), 16)
+        );
+        assert_eq!(
+            resolve(&mut project, &"dummy:5".parse().unwrap()).unwrap(),
+            (String::from("dummy"), 5)
+        );
+        assert!(matches!(
+            resolve(&mut project, &"child:5".parse().unwrap()),
+            Err(Error::TaskNotFound { .. })
+        ));
+        assert!(matches!(
+            resolve(&mut project, &"nope:5".parse().unwrap()),
+            Err(Error::ProjectNotFound(_))
+        ));
+        assert_eq!(
+            resolve(&mut project, &"@foreign:4".parse().unwrap()).unwrap(),
+            (String::from("child"), 20)
+        );
+
+        assert_eq!("042".parse::<TaskRef>().unwrap(), TaskRef::Index(42));
+        assert_eq!(
+            "1234".parse::<TaskRef>().unwrap(),
+            TaskRef::Id(String::from("1234"))
+        );
+        assert_eq!(
+            "my:sub:007".parse::<TaskRef>().unwrap(),
+            TaskRef::InProject(String::from("my:sub"), 7)
+        );
+        assert_eq!(
+            "@042".parse::<TaskRef>().unwrap(),
+            TaskRef::Id(String::from("042"))
+        );
+        assert!(":007".parse::<TaskRef>().is_err());
+        assert!("sub:1234".parse::<TaskRef>().is_err());
+        assert!("@".parse::<TaskRef>().is_err());
+    }
+
     #[test]
     fn save() {
         let tmpfile = NamedTempFile::new().expect("unable to create tmpfile");
@@ -284,10 +1118,29 @@ mod tests {
             false,
         );
 
-        project.add("hypa hypa".to_string(), false);
-        project.add("HYPA HYPA".to_string(), true);
+        project.add("hypa hypa".to_string(), false).unwrap();
+        project.add("HYPA HYPA".to_string(), true).unwrap();
+
+        project.save().expect("unable to save project");
+    }
+
+    #[test]
+    fn save_child() {
+        let tmpfile = NamedTempFile::new().expect("unable to create tmpfile");
+
+        let mut project = Project::new(
+            tmpfile.path().to_path_buf(),
+            1,
+            String::from("childproject"),
+            true,
+        );
 
+        project.add("rock".to_string(), false).unwrap();
         project.save().expect("unable to save project");
+
+        let loaded =
+            Project::load(tmpfile.path().to_path_buf(), 1).expect("unable to load project");
+        assert_eq!(loaded.data, project.data);
     }
 
     #[test]
@@ -299,13 +1152,13 @@ mod tests {
             false,
         );
 
-        project.add("iam".to_string(), false);
-        project.add("root".to_string(), true);
+        project.add("iam".to_string(), false).unwrap();
+        project.add("root".to_string(), true).unwrap();
 
         project.remove(0);
 
         assert!(project.get_task_mut(0).is_err());
-        assert_eq!(project.next_index(), 2);
+        assert_eq!(project.next_index().unwrap(), 2);
     }
 
     #[test]
@@ -317,11 +1170,11 @@ mod tests {
             false,
         );
 
-        project.add("never".to_string(), true);
-        project.add("gonna".to_string(), false);
-        project.add("give".to_string(), false);
-        project.add("you".to_string(), true);
-        project.add("up".to_string(), false);
+        project.add("never".to_string(), true).unwrap();
+        project.add("gonna".to_string(), false).unwrap();
+        project.add("give".to_string(), false).unwrap();
+        project.add("you".to_string(), true).unwrap();
+        project.add("up".to_string(), false).unwrap();
 
         project.remove_completed();
 
@@ -335,7 +1188,7 @@ mod tests {
         project.data.tasks.push(Task::new("a", false, 5));
         project.data.tasks.push(Task::new("a", false, 16));
 
-        assert_eq!(project.next_index(), 17);
+        assert_eq!(project.next_index().unwrap(), 17);
     }
 
     #[test]
@@ -344,7 +1197,42 @@ mod tests {
         project.data.tasks.push(Task::new("a", false, 999));
         project.data.tasks.push(Task::new("a", false, 3));
 
-        assert_eq!(project.next_index(), 0);
+        assert_eq!(project.next_index().unwrap(), 0);
+    }
+
+    #[test]
+    fn move_and_sort() {
+        let mut project = Project::new(PathBuf::new(), 0, String::from("dummy"), false);
+        project.add("b".to_string(), false).unwrap().due = NaiveDate::from_ymd_opt(2026, 10, 20);
+        project.add("C".to_string(), false).unwrap().priority = Some(2);
+        project.add("a".to_string(), false).unwrap().due = NaiveDate::from_ymd_opt(2026, 10, 19);
+        project.add("d".to_string(), false).unwrap().priority = Some(1);
+
+        let order = |p: &Project| p.data.tasks.iter().map(|t| t.index).collect::<Vec<_>>();
+
+        project.move_task(3, Position::Before(1)).unwrap();
+        assert_eq!(order(&project), vec![0, 3, 1, 2]);
+        project.move_task(0, Position::Bottom).unwrap();
+        assert_eq!(order(&project), vec![3, 1, 2, 0]);
+        project.move_task(2, Position::Top).unwrap();
+        assert_eq!(order(&project), vec![2, 3, 1, 0]);
+        project.move_task(2, Position::Before(2)).unwrap();
+        assert_eq!(order(&project), vec![2, 3, 1, 0]);
+
+        assert!(project.move_task(7, Position::Top).is_err());
+        assert!(project.move_task(2, Position::Before(7)).is_err());
+        assert_eq!(order(&project), vec![2, 3, 1, 0]);
+
+        project.sort_tasks(SortKey::Index);
+        assert_eq!(order(&project), vec![0, 1, 2, 3]);
+        project.sort_tasks(SortKey::Desc);
+        assert_eq!(order(&project), vec![2, 0, 1, 3]);
+        project.sort_tasks(SortKey::Due);
+        assert_eq!(order(&project), vec![2, 0, 1, 3]);
+        project.sort_tasks(SortKey::Priority);
+        assert_eq!(order(&project), vec![3, 1, 2, 0]);
+
+        assert!("size".parse::<SortKey>().is_err());
     }
 
     #[test]
@@ -355,21 +1243,90 @@ mod tests {
         let mut child2_1 = Project::new(PathBuf::new(), 2, String::from("child2_1"), true);
 
         // create tasks
-        child1.add("wegot".to_string(), false);
-        child2_1.add("themoves".to_string(), false);
+        child1.add("wegot".to_string(), false).unwrap();
+        child2_1.add("themoves".to_string(), false).unwrap();
 
         // attach children
         child2.attach_child(child2_1);
         root.attach_child(child1);
         root.attach_child(child2);
 
+        assert_eq!(root.find_task("child1", 0).unwrap().desc, "wegot");
+        assert_eq!(root.find_task("child2_1", 0).unwrap().desc, "themoves");
+        // the exact name wins over child2_1
+        assert_eq!(root.select_mut("child2").unwrap().data.name, "child2");
+
+        assert!(matches!(
+            root.find_task("child", 0),
+            Err(Error::AmbiguousProject { candidates, .. }) if candidates.len() == 3
+        ));
+        assert!(matches!(
+            root.find_task("nope", 0),
+            Err(Error::ProjectNotFound(_))
+        ));
+        assert!(root.duplicate_names().is_empty());
+
+        root.attach_child(Project::new(
+            PathBuf::new(),
+            1,
+            String::from("child1"),
+            true,
+        ));
+        assert_eq!(root.duplicate_names(), vec!["child1"]);
+        assert!(matches!(
+            root.find_task("child1", 0),
+            Err(Error::AmbiguousProject { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join(crate::PROJECT_FILE_NAME);
+        std::fs::write(
+            &path,
+            r#"
+            name = "hooks"
+            tasks = []
+
+            [hooks]
+            pre-save = "! grep -q forbidden"
+            on-add = ["cat >> added.json", "echo $TUTEL_EVENT >> events"]
+            "#,
+        )
+        .unwrap();
+
+        // hooks from project files only run when allowed
+        let mut p = Project::load(path.clone(), 0).unwrap();
+        p.add(String::from("forbidden"), false).unwrap();
+        p.save().unwrap();
+        assert!(!tmpdir.path().join("added.json").exists());
+
+        let settings = std::sync::Arc::new(crate::hooks::Settings {
+            project_hooks: true,
+            ..crate::hooks::Settings::default()
+        });
+        let mut p = Project::load(path.clone(), 0).unwrap();
+        p.set_hooks(&settings);
+
+        p.add(String::from("also forbidden"), false).unwrap();
+        assert!(matches!(p.save(), Err(Error::HookRejected { .. })));
+        assert_eq!(Project::load(path.clone(), 0).unwrap().data.tasks.len(), 1);
+
+        p.data.tasks.pop();
+        p.add(String::from("fine"), false).unwrap();
+        p.save().unwrap();
+
+        let added = std::fs::read_to_string(tmpdir.path().join("added.json")).unwrap();
+        assert!(added.contains(r#""desc":"fine""#));
+        let events = std::fs::read_to_string(tmpdir.path().join("events")).unwrap();
+        assert_eq!(events, "on-add\n");
+
+        // the hooks survive saving
         assert_eq!(
-            *root.find_task("child1", 0).unwrap(),
-            Task::new("wegot", false, 0)
-        );
-        assert_eq!(
-            *root.find_task("child2_1", 0).unwrap(),
-            Task::new("themoves", false, 0)
+            Project::load(path, 0).unwrap().data.hooks.on_add,
+            ["cat >> added.json", "echo $TUTEL_EVENT >> events"]
         );
     }
 }
@@ -0,0 +1,250 @@
+//! A compact summary of the nearest project for shell prompts.
+//!
+//! Unlike [`crate::load_project_rec`], only the project file closest to the
+//! directory is read, children are not looked for and nothing is written.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use chrono::NaiveDate;
+
+use crate::{agenda::Bucket, storage::FileStorage, Result, Storage, PROJECT_FILE_NAME};
+
+/// Used when no format is given
+pub const DEFAULT_FORMAT: &str = "{done}/{total}( ⚑{overdue})";
+
+/// Task counts of a single project
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub name: String,
+    pub open: usize,
+    pub done: usize,
+    pub overdue: usize,
+    pub today: usize,
+}
+
+impl Summary {
+    /// Replaces the placeholders `{name}`, `{open}`, `{done}`, `{total}`,
+    /// `{overdue}` and `{today}` in `format`.
+    ///
+    /// Text in parentheses is only kept if one of the placeholders in it is
+    /// not zero, `(⚑{overdue} overdue)` disappears when nothing is overdue.
+    #[must_use]
+    pub fn format(&self, format: &str) -> String {
+        let mut result = String::new();
+        let mut group: Option<(String, bool)> = None;
+        let mut rest = format;
+
+        while let Some(c) = rest.chars().next() {
+            let (text, shown) = match c {
+                '(' if group.is_none() => {
+                    group = Some((String::new(), false));
+                    rest = &rest[1..];
+                    continue;
+                }
+                ')' if group.is_some() => {
+                    if let Some((text, true)) = group.take() {
+                        result.push_str(&text);
+                    }
+                    rest = &rest[1..];
+                    continue;
+                }
+                '{' => {
+                    let placeholder = rest
+                        .find('}')
+                        .and_then(|end| Some((end, self.value(&rest[1..end])?)));
+
+                    if let Some((end, value)) = placeholder {
+                        rest = &rest[end + 1..];
+                        value
+                    } else {
+                        rest = &rest[1..];
+                        (String::from("{"), false)
+                    }
+                }
+                c => {
+                    rest = &rest[c.len_utf8()..];
+                    (c.to_string(), false)
+                }
+            };
+
+            match &mut group {
+                Some((group, any)) => {
+                    group.push_str(&text);
+                    *any |= shown;
+                }
+                None => result.push_str(&text),
+            }
+        }
+
+        // an unclosed group is kept as it was written
+        if let Some((text, _)) = group {
+            result.push('(');
+            result.push_str(&text);
+        }
+
+        result
+    }
+
+    /// The value of a placeholder and whether it keeps its group
+    fn value(&self, name: &str) -> Option<(String, bool)> {
+        let count = match name {
+            "name" => return Some((self.name.clone(), !self.name.is_empty())),
+            "open" => self.open,
+            "done" => self.done,
+            "total" => self.open + self.done,
+            "overdue" => self.overdue,
+            "today" => self.today,
+            _ => return None,
+        };
+
+        Some((count.to_string(), count != 0))
+    }
+}
+
+/// Returns the project file closest to `dir`, in it or one of its parents
+#[must_use]
+pub fn nearest(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(PROJECT_FILE_NAME))
+        .find(|f| f.is_file())
+}
+
+/// Summarizes the project closest to `dir`. Returns `None` if there is none.
+///
+/// # Errors
+/// This function will return an error if the project file could not be read.
+pub fn summary(dir: &Path, today: NaiveDate) -> Result<Option<Summary>> {
+    let Some(file) = nearest(dir) else {
+        return Ok(None);
+    };
+
+    let (data, _) = FileStorage.read(&file)?;
+    let mut summary = Summary {
+        name: data.name,
+        ..Summary::default()
+    };
+
+    for task in &data.tasks {
+        if task.completed {
+            summary.done += 1;
+            continue;
+        }
+
+        summary.open += 1;
+        match Bucket::of(task.due, today) {
+            Bucket::Overdue => summary.overdue += 1,
+            Bucket::Today => summary.today += 1,
+            _ => {}
+        }
+    }
+
+    Ok(Some(summary))
+}
+
+/// Like [`summary`], but gives up and returns `None` after `budget`, so a
+/// slow file system never holds up the prompt. Errors are treated like
+/// there was no project.
+#[must_use]
+pub fn summary_within(dir: &Path, today: NaiveDate, budget: Duration) -> Option<Summary> {
+    let (tx, rx) = mpsc::channel();
+    let dir = dir.to_path_buf();
+
+    // the thread is left behind if it takes too long, the process is about
+    // to exit anyway
+    thread::spawn(move || {
+        let _ = tx.send(summary(&dir, today));
+    });
+
+    rx.recv_timeout(budget).ok()?.ok()?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use chrono::NaiveDate;
+
+    use super::{summary_within, Summary};
+    use crate::PROJECT_FILE_NAME;
+
+    #[test]
+    fn format() {
+        let summary = Summary {
+            name: String::from("tutel"),
+            open: 4,
+            done: 3,
+            overdue: 2,
+            today: 0,
+        };
+
+        assert_eq!(summary.format("{done}/{total}"), "3/7");
+        assert_eq!(summary.format("(⚑{overdue} overdue)"), "⚑2 overdue");
+        assert_eq!(summary.format("{name}( !{today})"), "tutel");
+        assert_eq!(summary.format("{open}( {what}) (x"), "4 (x");
+    }
+
+    #[test]
+    fn nearest_project_only() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let budget = Duration::from_secs(5);
+        let sub = tmpdir.path().join("sub/deeper");
+        fs::create_dir_all(&sub).unwrap();
+
+        assert_eq!(summary_within(&sub, today, budget), None);
+
+        fs::write(
+            tmpdir.path().join(PROJECT_FILE_NAME),
+            r#"
+            name = "root"
+            is_child = false
+
+            [[tasks]]
+            index = 0
+            desc = "old"
+            completed = false
+            due = "2026-10-01"
+
+            [[tasks]]
+            index = 1
+            desc = "now"
+            completed = false
+            due = "2026-10-18"
+
+            [[tasks]]
+            index = 2
+            desc = "done"
+            completed = true
+            due = "2026-10-01"
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            tmpdir.path().join("sub").join(PROJECT_FILE_NAME),
+            "name = \"sub\"\nis_child = true\ntasks = []\n",
+        )
+        .unwrap();
+
+        let root = summary_within(tmpdir.path(), today, budget).unwrap();
+        assert_eq!(
+            root,
+            Summary {
+                name: String::from("root"),
+                open: 2,
+                done: 1,
+                overdue: 1,
+                today: 1,
+            }
+        );
+
+        // children are not included, and the nearest project wins
+        let sub = summary_within(&sub, today, budget).unwrap();
+        assert_eq!(sub.name, "sub");
+        assert_eq!(sub.open + sub.done, 0);
+    }
+}
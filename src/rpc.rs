@@ -0,0 +1,708 @@
+//! A JSON-RPC 2.0 server that keeps a project tree loaded, for editor plugins
+//! and status bars. Every message is a single line of JSON.
+//!
+//! Methods:
+//! - `list`: the whole tree
+//! - `add`: `{"desc", "project"?, "completed"?, "due"?, "tags"?}`, returns the task
+//! - `complete`: `{"task", "project"?, "completed"?}`, returns the task
+//! - `edit`: `{"task", "project"?, "desc"?, "completed"?}`, changes both at
+//!   once, returns the task
+//! - `remove`: `{"task", "project"?}`, returns the removed task
+//! - `subscribe`: the connection receives a `changed` notification with the
+//!   whole tree whenever it changes
+//!
+//! `task` is an index or an id prefix of a task anywhere below `project`,
+//! `project` the name of a project of the tree, or a prefix matching only
+//! one, and the root project if omitted.
+
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc, Mutex, MutexGuard, PoisonError},
+};
+#[cfg(unix)]
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    sync::mpsc,
+    thread,
+};
+
+use chrono::NaiveDate;
+use serde_json::{json, Map, Value};
+
+#[cfg(unix)]
+use crate::watch::Watcher;
+use crate::{
+    error::{Error, Result},
+    hooks, load_project_rec_from,
+    storage::FileStorage,
+    Project, Storage, Task, TaskRef,
+};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// Loading or saving the project failed
+pub const PROJECT_ERROR: i64 = -32000;
+pub const TASK_NOT_FOUND: i64 = -32001;
+/// The project was changed by someone else while saving
+pub const CONFLICT: i64 = -32002;
+
+/// Where notifications for a subscriber are queued
+///
+/// One message without the trailing newline each. Whoever receives them
+/// writes them to the client, so a client that stops reading never holds up
+/// the server.
+pub type Subscriber = Sender<String>;
+
+/// The error of a request, one of the error codes above
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+impl From<Error> for RpcError {
+    fn from(e: Error) -> Self {
+        let code = match e {
+            Error::TaskNotFound { .. } => TASK_NOT_FOUND,
+            // a pre-save hook rejecting the change is the client's business
+            Error::AmbiguousTask { .. }
+            | Error::ProjectNotFound(_)
+            | Error::AmbiguousProject { .. }
+            | Error::HookRejected { .. } => INVALID_PARAMS,
+            Error::LockConflict(_) => CONFLICT,
+            _ => PROJECT_ERROR,
+        };
+
+        Self::new(code, e.to_string())
+    }
+}
+
+struct State {
+    project: Project,
+    subscribers: Vec<Subscriber>,
+}
+
+/// Keeps the project tree rooted at a directory loaded and answers requests
+pub struct Server {
+    dir: PathBuf,
+    storage: Arc<dyn Storage>,
+    hooks: Arc<hooks::Settings>,
+    state: Mutex<State>,
+}
+
+impl Server {
+    /// Loads the project tree at `dir` from the file system
+    ///
+    /// # Errors
+    /// This function will return an error if the project tree could not be
+    /// loaded.
+    pub fn new(dir: &Path) -> Result<Self> {
+        Self::with_storage(Arc::new(FileStorage), dir)
+    }
+
+    /// Loads the project tree at `dir` from `storage`
+    ///
+    /// # Errors
+    /// This function will return an error if the project tree could not be
+    /// loaded.
+    pub fn with_storage(storage: Arc<dyn Storage>, dir: &Path) -> Result<Self> {
+        let project = load_project_rec_from(&storage, dir)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            storage,
+            hooks: Arc::default(),
+            state: Mutex::new(State {
+                project,
+                subscribers: Vec::new(),
+            }),
+        })
+    }
+
+    /// Runs the hooks from the user configuration whenever the project tree
+    /// is saved
+    #[must_use]
+    pub fn with_hooks(mut self, settings: hooks::Settings) -> Self {
+        self.hooks = Arc::new(settings);
+        self.lock().project.set_hooks(&self.hooks);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Listens on the Unix socket at `socket` until the process is stopped.
+    /// A stale socket file left behind by a previous server is replaced.
+    ///
+    /// # Errors
+    /// This function will return an error if the socket could not be bound,
+    /// e.g. because another server is already listening on it.
+    #[cfg(unix)]
+    pub fn serve(self: Arc<Self>, socket: &Path) -> Result<()> {
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(Error::io(
+                    socket,
+                    io::Error::new(io::ErrorKind::AddrInUse, "a server is already listening"),
+                ));
+            }
+            std::fs::remove_file(socket).map_err(|e| Error::io(socket, e))?;
+        }
+
+        let listener = UnixListener::bind(socket).map_err(|e| Error::io(socket, e))?;
+
+        let server = Arc::clone(&self);
+        thread::spawn(move || {
+            let mut watcher = Watcher::new(&server.dir);
+            loop {
+                watcher.wait();
+                let mut state = server.lock();
+                // our own saves wake the watcher too, they leave nothing stale
+                if state.project.is_stale() {
+                    // a broken project file is reported to the next request
+                    let _ = server.reload(&mut state, false);
+                }
+            }
+        });
+
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+
+            let server = Arc::clone(&self);
+            thread::spawn(move || server.connection(stream));
+        }
+
+        Ok(())
+    }
+
+    /// Answers the requests of one client. Notifications are queued and
+    /// written by a thread of their own, the writer lock keeps them from
+    /// ending up in the middle of a response.
+    #[cfg(unix)]
+    fn connection(&self, stream: UnixStream) {
+        let Ok(reader) = stream.try_clone() else {
+            return;
+        };
+        let writer = Arc::new(Mutex::new(stream));
+        let write = |writer: &Mutex<UnixStream>, message: &str| {
+            let mut stream = writer.lock().unwrap_or_else(PoisonError::into_inner);
+            stream.write_all(format!("{message}\n").as_bytes())
+        };
+
+        let (subscriber, notifications) = mpsc::channel::<String>();
+        let notifier = Arc::clone(&writer);
+        thread::spawn(move || {
+            for notification in notifications {
+                if write(&notifier, &notification).is_err() {
+                    // dropping the receiver unsubscribes
+                    return;
+                }
+            }
+        });
+
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if let Some(response) = self.handle(&line, &|| subscriber.clone()) {
+                if write(&writer, &response).is_err() {
+                    break;
+                }
+            }
+        }
+
+        // lets the notification thread end with the next notification
+        let stream = writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+
+    /// Answers a single JSON-RPC message. Returns None for notifications,
+    /// which don't get a response. `subscriber` is called when the client
+    /// subscribes to changes.
+    pub fn handle(&self, message: &str, subscriber: &dyn Fn() -> Subscriber) -> Option<String> {
+        let request: Value = match serde_json::from_str(message) {
+            Ok(request) => request,
+            Err(e) => {
+                return Some(error_response(
+                    &Value::Null,
+                    &RpcError::new(PARSE_ERROR, e.to_string()),
+                ))
+            }
+        };
+
+        let id = request.get("id").cloned();
+        let result = self.dispatch(&request, subscriber);
+
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string(),
+            Err(e) => error_response(&id, &e),
+        })
+    }
+
+    fn dispatch(
+        &self,
+        request: &Value,
+        subscriber: &dyn Fn() -> Subscriber,
+    ) -> std::result::Result<Value, RpcError> {
+        if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return Err(RpcError::new(
+                INVALID_REQUEST,
+                "expected a JSON-RPC 2.0 request",
+            ));
+        }
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return Err(RpcError::new(INVALID_REQUEST, "method is missing"));
+        };
+        let empty = Map::new();
+        let params = match request.get("params") {
+            None | Some(Value::Null) => &empty,
+            Some(Value::Object(params)) => params,
+            Some(_) => return Err(RpcError::invalid_params("params have to be an object")),
+        };
+
+        if method == "subscribe" {
+            self.lock().subscribers.push(subscriber());
+            return Ok(Value::Bool(true));
+        }
+
+        self.call(method, params)
+    }
+
+    /// Runs `method` on the tree, see the module documentation. Changes are
+    /// saved right away.
+    ///
+    /// # Errors
+    /// This function will return an error if the method doesn't exist, the
+    /// params don't fit or the tree could not be loaded or saved.
+    pub fn call(
+        &self,
+        method: &str,
+        params: &Map<String, Value>,
+    ) -> std::result::Result<Value, RpcError> {
+        let mut state = self.lock();
+        // changes made with the CLI in the meantime would be overwritten otherwise
+        if state.project.is_stale() {
+            self.reload(&mut state, true)?;
+        }
+
+        let result = match method {
+            "list" => return Ok(project_json(&state.project)),
+            "add" => add(&mut state.project, params)?,
+            "complete" => complete(&mut state.project, params)?,
+            "edit" => edit(&mut state.project, params)?,
+            "remove" => remove(&mut state.project, params)?,
+            _ => {
+                return Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("unknown method: {method}"),
+                ))
+            }
+        };
+
+        if let Err(e) = state.project.save() {
+            // don't keep a change that isn't saved
+            let _ = self.reload(&mut state, true);
+            return Err(e.into());
+        }
+        notify(&mut state);
+        drop(state);
+
+        Ok(result)
+    }
+
+    /// Loads the project tree again, notifying subscribers if it changed.
+    /// With `force`, the loaded tree is kept even if it looks the same.
+    /// Takes the locked state so nothing changes between loading and
+    /// replacing the tree.
+    fn reload(&self, state: &mut State, force: bool) -> Result<()> {
+        let mut project = load_project_rec_from(&self.storage, &self.dir)?;
+        project.set_hooks(&self.hooks);
+
+        if project != state.project {
+            state.project = project;
+            notify(state);
+        } else if force {
+            state.project = project;
+        }
+
+        Ok(())
+    }
+}
+
+/// Queues the tree for every subscriber, dropping those that went away
+fn notify(state: &mut State) {
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "changed",
+        "params": project_json(&state.project),
+    })
+    .to_string();
+
+    state
+        .subscribers
+        .retain(|s| s.send(notification.clone()).is_ok());
+}
+
+fn error_response(id: &Value, error: &RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+    .to_string()
+}
+
+fn project_json(project: &Project) -> Value {
+    json!({
+        "name": project.data.name,
+        "path": project.path(),
+        "tasks": project.data.tasks.iter().map(task_json).collect::<Vec<_>>(),
+        "children": project.get_children().iter().map(project_json).collect::<Vec<_>>(),
+    })
+}
+
+fn task_json(task: &Task) -> Value {
+    serde_json::to_value(task).unwrap_or(Value::Null)
+}
+
+fn str_param<'a>(
+    params: &'a Map<String, Value>,
+    name: &str,
+) -> std::result::Result<Option<&'a str>, RpcError> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(RpcError::invalid_params(format!(
+            "{name} has to be a string"
+        ))),
+    }
+}
+
+fn required_str<'a>(
+    params: &'a Map<String, Value>,
+    name: &str,
+) -> std::result::Result<&'a str, RpcError> {
+    str_param(params, name)?.ok_or_else(|| RpcError::invalid_params(format!("{name} is missing")))
+}
+
+fn bool_param(
+    params: &Map<String, Value>,
+    name: &str,
+) -> std::result::Result<Option<bool>, RpcError> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(value)) => Ok(Some(*value)),
+        Some(_) => Err(RpcError::invalid_params(format!(
+            "{name} has to be a boolean"
+        ))),
+    }
+}
+
+/// The project named by the `project` param, the root if there is none
+fn target<'a>(
+    project: &'a mut Project,
+    params: &Map<String, Value>,
+) -> std::result::Result<&'a mut Project, RpcError> {
+    match str_param(params, "project")? {
+        None => Ok(project),
+        Some(name) => Ok(project.select_mut(name)?),
+    }
+}
+
+/// The project and index of the task named by the `task` param
+fn task_param<'a>(
+    project: &'a mut Project,
+    params: &Map<String, Value>,
+) -> std::result::Result<(&'a mut Project, usize), RpcError> {
+    let task = match params.get("task") {
+        Some(Value::Number(index)) => index
+            .as_u64()
+            .and_then(|i| usize::try_from(i).ok())
+            .map(TaskRef::Index),
+        Some(Value::String(task)) => task.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| RpcError::invalid_params("task has to be an index or an id prefix"))?;
+
+    Ok(target(project, params)?.resolve(&task)?)
+}
+
+fn add(project: &mut Project, params: &Map<String, Value>) -> std::result::Result<Value, RpcError> {
+    let desc = required_str(params, "desc")?.to_string();
+    let completed = bool_param(params, "completed")?.unwrap_or(false);
+    let due = str_param(params, "due")?
+        .map(|due| {
+            NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                .map_err(|_| RpcError::invalid_params(format!("not a valid date: {due}")))
+        })
+        .transpose()?;
+    let tags = match params.get("tags") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(tags) => serde_json::from_value(tags.clone())
+            .map_err(|_| RpcError::invalid_params("tags have to be strings"))?,
+    };
+
+    let task = target(project, params)?.add(desc, completed)?;
+    task.due = due;
+    task.tags = tags;
+
+    Ok(task_json(task))
+}
+
+fn complete(
+    project: &mut Project,
+    params: &Map<String, Value>,
+) -> std::result::Result<Value, RpcError> {
+    let completed = bool_param(params, "completed")?.unwrap_or(true);
+    let (project, index) = task_param(project, params)?;
+
+    let task = project.get_task_mut(index)?;
+    task.set_completed(completed);
+
+    Ok(task_json(task))
+}
+
+fn edit(
+    project: &mut Project,
+    params: &Map<String, Value>,
+) -> std::result::Result<Value, RpcError> {
+    let desc = str_param(params, "desc")?.map(String::from);
+    let completed = bool_param(params, "completed")?;
+    if desc.is_none() && completed.is_none() {
+        return Err(RpcError::invalid_params(
+            "nothing to change, expected desc or completed",
+        ));
+    }
+    let (project, index) = task_param(project, params)?;
+
+    let task = project.get_task_mut(index)?;
+    if let Some(desc) = desc {
+        task.desc = desc;
+    }
+    if let Some(completed) = completed {
+        task.set_completed(completed);
+    }
+
+    Ok(task_json(task))
+}
+
+fn remove(
+    project: &mut Project,
+    params: &Map<String, Value>,
+) -> std::result::Result<Value, RpcError> {
+    let (project, index) = task_param(project, params)?;
+
+    let removed = task_json(project.get_task_mut(index)?);
+    project.remove(index);
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+    use std::{
+        path::{Path, PathBuf},
+        sync::{mpsc, Arc},
+    };
+
+    use super::{Server, Subscriber};
+    use crate::{
+        storage::{MemoryStorage, Storage},
+        Project, PROJECT_FILE_NAME,
+    };
+
+    fn server() -> (Arc<dyn Storage>, Server) {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let mut root = Project::with_storage(
+            storage.clone(),
+            PathBuf::from("/p").join(PROJECT_FILE_NAME),
+            0,
+            String::from("root"),
+            false,
+        );
+        root.add(String::from("first"), false).unwrap();
+        root.save().unwrap();
+
+        let mut child = Project::with_storage(
+            storage.clone(),
+            PathBuf::from("/p/c").join(PROJECT_FILE_NAME),
+            1,
+            String::from("child"),
+            true,
+        );
+        child.save().unwrap();
+
+        let server = Server::with_storage(storage.clone(), Path::new("/p")).unwrap();
+        (storage, server)
+    }
+
+    fn call(server: &Server, request: &Value) -> Value {
+        let no_subscriber = || -> Subscriber { mpsc::channel().0 };
+        let response = server.handle(&request.to_string(), &no_subscriber).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn request(id: u64, method: &str, params: &Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    #[test]
+    fn requests() {
+        let (storage, server) = server();
+
+        let list = call(&server, &request(1, "list", &Value::Null));
+        assert_eq!(list["id"], 1);
+        assert_eq!(list["result"]["name"], "root");
+        assert_eq!(list["result"]["tasks"][0]["desc"], "first");
+        assert_eq!(list["result"]["children"][0]["name"], "child");
+
+        let added = call(
+            &server,
+            &request(
+                2,
+                "add",
+                &json!({"desc": "second", "project": "child", "tags": ["a"]}),
+            ),
+        );
+        assert_eq!(added["result"]["index"], 0);
+        assert_eq!(added["result"]["tags"], json!(["a"]));
+
+        let done = call(
+            &server,
+            &request(3, "complete", &json!({"task": 0, "project": "child"})),
+        );
+        assert_eq!(done["result"]["completed"], true);
+
+        let edited = call(
+            &server,
+            &request(4, "edit", &json!({"task": "0", "desc": "renamed"})),
+        );
+        assert_eq!(edited["result"]["desc"], "renamed");
+
+        // writes go through Project::save
+        let stored = storage
+            .read(&PathBuf::from("/p/c").join(PROJECT_FILE_NAME))
+            .unwrap()
+            .0;
+        assert!(stored.tasks[0].completed);
+
+        let removed = call(&server, &request(5, "remove", &json!({"task": 0})));
+        assert_eq!(removed["result"]["desc"], "renamed");
+        let list = call(&server, &request(6, "list", &Value::Null));
+        assert_eq!(list["result"]["tasks"], json!([]));
+    }
+
+    #[test]
+    fn errors() {
+        let (_, server) = server();
+        let code = |response: Value| response["error"]["code"].as_i64().unwrap();
+
+        let no_subscriber = || -> Subscriber { mpsc::channel().0 };
+        let response = server.handle("{not json", &no_subscriber).unwrap();
+        assert_eq!(code(serde_json::from_str(&response).unwrap()), -32700);
+
+        assert_eq!(
+            code(call(&server, &json!({"id": 1, "method": "list"}))),
+            -32600
+        );
+        assert_eq!(
+            code(call(&server, &request(1, "fly", &Value::Null))),
+            -32601
+        );
+        assert_eq!(code(call(&server, &request(1, "add", &json!({})))), -32602);
+        assert_eq!(
+            code(call(
+                &server,
+                &request(1, "add", &json!({"desc": "x", "project": "nope"}))
+            )),
+            -32602
+        );
+        assert_eq!(
+            code(call(&server, &request(1, "remove", &json!({"task": 42})))),
+            -32001
+        );
+
+        // notifications don't get a response
+        let notification = json!({"jsonrpc": "2.0", "method": "list"}).to_string();
+        assert_eq!(server.handle(&notification, &no_subscriber), None);
+    }
+
+    #[test]
+    fn subscribe_and_external_changes() {
+        let (storage, server) = server();
+
+        let (sender, receiver) = mpsc::channel();
+        let subscriber = move || -> Subscriber { sender.clone() };
+        let response = server
+            .handle(
+                &request(1, "subscribe", &Value::Null).to_string(),
+                &subscriber,
+            )
+            .unwrap();
+        assert!(response.contains("\"result\":true"));
+
+        // someone else changes the project in the meantime
+        let path = PathBuf::from("/p").join(PROJECT_FILE_NAME);
+        let mut other = Project::load_from(storage.clone(), path, 0).unwrap();
+        other.add(String::from("external"), false).unwrap();
+        other.save().unwrap();
+
+        call(&server, &request(2, "add", &json!({"desc": "mine"})));
+        let list = call(&server, &request(3, "list", &Value::Null));
+        let tasks: Vec<_> = list["result"]["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["desc"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(tasks, vec!["first", "external", "mine"]);
+
+        let notifications: Vec<Value> = receiver
+            .try_iter()
+            .map(|message| serde_json::from_str(&message).unwrap())
+            .collect();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[1]["method"], "changed");
+        assert_eq!(notifications[1]["params"]["tasks"][2]["desc"], "mine");
+
+        // both changes are saved at once
+        let edited = call(
+            &server,
+            &request(
+                4,
+                "edit",
+                &json!({"task": 2, "desc": "all mine", "completed": true}),
+            ),
+        );
+        assert_eq!(
+            (&edited["result"]["desc"], &edited["result"]["completed"]),
+            (&json!("all mine"), &json!(true))
+        );
+        assert_eq!(receiver.try_iter().count(), 1);
+    }
+}
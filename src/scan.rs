@@ -0,0 +1,277 @@
+//! Finds TODO/FIXME/HACK comments in source code.
+
+use std::{fs, path::Path};
+
+use ignore::WalkBuilder;
+
+use crate::{Project, PROJECT_FILE_NAME};
+
+/// The words that mark a comment as something to do
+pub const MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+/// Tokens that start a comment in common languages
+const COMMENT_TOKENS: &[&str] = &["//", "/*", "<!--", "#", "--", ";", "%"];
+
+/// Files larger than this are not scanned
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// A marker comment found in a source file. These are read-only, they go
+/// away once the comment is removed from the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeMarker {
+    /// One of [`MARKERS`]
+    pub kind: String,
+    pub text: String,
+    /// Relative to the directory of the project file, always using `/`
+    pub file: String,
+    pub line: usize,
+}
+
+impl CodeMarker {
+    /// Whether two markers refer to the same comment, which may have moved
+    /// to another line.
+    #[must_use]
+    pub fn same_comment(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.text == other.text && self.file == other.file
+    }
+}
+
+/// What changed when syncing markers with the code
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    pub added: Vec<CodeMarker>,
+    pub resolved: Vec<CodeMarker>,
+}
+
+/// Replaces the markers of `project` and all of its children with what a
+/// fresh scan of their directories finds.
+///
+/// Files ignored by `.gitignore`, `.ignore` and similar files are skipped,
+/// as are directories containing another project.
+pub fn sync(project: &mut Project) -> ScanSummary {
+    let mut summary = ScanSummary::default();
+    sync_impl(project, &mut summary);
+    summary
+}
+
+fn sync_impl(project: &mut Project, summary: &mut ScanSummary) {
+    if let Some(dir) = project.path().parent() {
+        let found = scan_dir(dir);
+
+        for old in &project.data.code {
+            if !found.iter().any(|m| m.same_comment(old)) {
+                summary.resolved.push(old.clone());
+            }
+        }
+
+        for new in &found {
+            if !project.data.code.iter().any(|m| m.same_comment(new)) {
+                summary.added.push(new.clone());
+            }
+        }
+
+        project.data.code = found;
+    }
+
+    for child in project.get_children_mut() {
+        sync_impl(child, summary);
+    }
+}
+
+/// Scans all files below `dir`, except for those in ignored directories and
+/// directories containing another project.
+#[must_use]
+pub fn scan_dir(dir: &Path) -> Vec<CodeMarker> {
+    let root = dir.to_path_buf();
+    let walker = WalkBuilder::new(dir)
+        .filter_entry(move |entry| {
+            let path = entry.path();
+            path == root || !(path.is_dir() && path.join(PROJECT_FILE_NAME).is_file())
+        })
+        .build();
+
+    let mut markers = Vec::new();
+
+    for entry in walker.flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|t| t.is_file()) || path.ends_with(PROJECT_FILE_NAME) {
+            continue;
+        }
+
+        if entry.metadata().map_or(true, |m| m.len() > MAX_FILE_SIZE) {
+            continue;
+        }
+
+        // binary files and other non utf8 files are skipped
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+
+        let file = path
+            .strip_prefix(dir)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        for (i, line) in content.lines().enumerate() {
+            if let Some((kind, text)) = parse_line(line) {
+                markers.push(CodeMarker {
+                    kind: kind.to_string(),
+                    text,
+                    file: file.clone(),
+                    line: i + 1,
+                });
+            }
+        }
+    }
+
+    markers.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    markers
+}
+
+/// Looks for a marker at the start of a comment. Returns the marker and the
+/// rest of the comment.
+fn parse_line(line: &str) -> Option<(&'static str, String)> {
+    let trimmed = line.trim_start();
+
+    // continuation lines of block comments
+    if let Some(rest) = trimmed.strip_prefix('*') {
+        if let Some(found) = parse_comment(rest) {
+            return Some(found);
+        }
+    }
+
+    for token in COMMENT_TOKENS {
+        for (start, _) in line.match_indices(token) {
+            if let Some(found) = parse_comment(&line[start + token.len()..]) {
+                return Some(found);
+            }
+        }
+    }
+
+    None
+}
+
+/// Parses the text following a comment token
+fn parse_comment(comment: &str) -> Option<(&'static str, String)> {
+    // doc comments, banners like '####' and the like
+    let comment = comment.trim_start_matches(|c: char| "/*#-;%!".contains(c));
+    let comment = comment.trim_start();
+
+    let kind = MARKERS.iter().find(|m| comment.starts_with(*m))?;
+    let mut rest = &comment[kind.len()..];
+
+    // TODOs are not part of longer words, e.g. TODOS
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    // TODO(someone): ...
+    if rest.starts_with('(') {
+        rest = rest.find(')').map_or("", |end| &rest[end + 1..]);
+    }
+
+    let text = rest
+        .trim_start_matches(|c: char| c == ':' || c.is_whitespace())
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end();
+
+    Some((kind, text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    use super::{parse_line, scan_dir, sync, CodeMarker};
+    use crate::{Project, PROJECT_FILE_NAME};
+
+    fn parsed(kind: &'static str, text: &str) -> (&'static str, String) {
+        (kind, text.to_string())
+    }
+
+    #[test]
+    fn comment_syntaxes() {
+        assert_eq!(
+            parse_line("    // TODO: un-hack me"),
+            Some(parsed("TODO", "un-hack me"))
+        );
+        assert_eq!(
+            parse_line("/// FIXME handle errors"),
+            Some(parsed("FIXME", "handle errors"))
+        );
+        assert_eq!(
+            parse_line("x = 1  # HACK(bob): why"),
+            Some(parsed("HACK", "why"))
+        );
+        assert_eq!(
+            parse_line("-- TODO drop table"),
+            Some(parsed("TODO", "drop table"))
+        );
+        assert_eq!(
+            parse_line("/* TODO: block */"),
+            Some(parsed("TODO", "block"))
+        );
+        assert_eq!(
+            parse_line(" * FIXME: inside block"),
+            Some(parsed("FIXME", "inside block"))
+        );
+        assert_eq!(
+            parse_line("<!-- TODO: html -->"),
+            Some(parsed("TODO", "html"))
+        );
+        assert_eq!(parse_line("; TODO"), Some(parsed("TODO", "")));
+
+        assert_eq!(parse_line("let todo = \"TODO: not a comment\";"), None);
+        assert_eq!(parse_line("// TODOS are not markers"), None);
+        assert_eq!(parse_line("// this is a TODO in the middle"), None);
+    }
+
+    #[test]
+    fn scan_and_sync() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let dir = tmpdir.path();
+
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("child")).unwrap();
+        fs::write(dir.join(".ignore"), "ignored.rs\n").unwrap();
+        fs::write(
+            dir.join("src/main.rs"),
+            "fn main() {}\n// TODO: un-hack me\n",
+        )
+        .unwrap();
+        fs::write(dir.join("ignored.rs"), "// TODO: ignored\n").unwrap();
+        fs::write(dir.join("child/lib.rs"), "// FIXME: belongs to the child\n").unwrap();
+        fs::write(dir.join("child").join(PROJECT_FILE_NAME), "").unwrap();
+
+        let marker = CodeMarker {
+            kind: String::from("TODO"),
+            text: String::from("un-hack me"),
+            file: String::from("src/main.rs"),
+            line: 2,
+        };
+
+        assert_eq!(scan_dir(dir), vec![marker.clone()]);
+
+        let mut project = Project::new(dir.join(PROJECT_FILE_NAME), 0, String::from("p"), false);
+        let summary = sync(&mut project);
+        assert_eq!(summary.added, vec![marker]);
+        assert!(summary.resolved.is_empty());
+
+        // moving the comment does not count as a change
+        fs::write(dir.join("src/main.rs"), "\n\n// TODO: un-hack me\n").unwrap();
+        let summary = sync(&mut project);
+        assert!(summary.added.is_empty() && summary.resolved.is_empty());
+        assert_eq!(project.data.code[0].line, 3);
+
+        fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let summary = sync(&mut project);
+        assert_eq!(summary.resolved.len(), 1);
+        assert!(project.data.code.is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Full-text search over the tasks of a project tree.
+
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::{Project, Task};
+
+/// What to search for
+#[derive(Debug, Clone)]
+pub struct Query {
+    regex: Regex,
+}
+
+impl Query {
+    /// Searches for `pattern`, which is taken literally unless `regex` is set.
+    ///
+    /// # Errors
+    /// This function will return an error if `pattern` is not a valid
+    /// regular expression.
+    pub fn new(pattern: &str, regex: bool, ignore_case: bool) -> Result<Self, regex::Error> {
+        let pattern = if regex {
+            pattern.to_owned()
+        } else {
+            regex::escape(pattern)
+        };
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()?;
+
+        Ok(Self { regex })
+    }
+
+    /// The byte ranges of all matches in `text`
+    #[must_use]
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .map(|m| m.range())
+            // an empty match has nothing to highlight
+            .filter(|r| !r.is_empty())
+            .collect()
+    }
+}
+
+/// A task whose description matches, together with the name of its project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub project: &'a str,
+    pub task: &'a Task,
+    /// Where the description matches, as byte ranges
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Searches the descriptions of the tasks of `project` and all of its
+/// children, in the order they are listed.
+#[must_use]
+pub fn search<'a>(project: &'a Project, query: &Query) -> Vec<Match<'a>> {
+    let mut matches = Vec::new();
+    search_rec(project, query, &mut matches);
+    matches
+}
+
+fn search_rec<'a>(project: &'a Project, query: &Query, matches: &mut Vec<Match<'a>>) {
+    for task in &project.data.tasks {
+        let ranges = query.find(&task.desc);
+        if !ranges.is_empty() {
+            matches.push(Match {
+                project: &project.data.name,
+                task,
+                ranges,
+            });
+        }
+    }
+
+    for child in project.get_children() {
+        search_rec(child, query, matches);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{search, Query};
+    use crate::Project;
+
+    #[test]
+    fn find() {
+        let literal = Query::new("a.b", false, false).unwrap();
+        assert_eq!(literal.find("a.b axb A.B a.b"), [0..3, 12..15]);
+
+        let ignore_case = Query::new("a.b", false, true).unwrap();
+        assert_eq!(ignore_case.find("a.b axb A.B"), [0..3, 8..11]);
+
+        let regex = Query::new("a.b", true, false).unwrap();
+        assert_eq!(regex.find("a.b axb A.B"), [0..3, 4..7]);
+
+        assert!(Query::new("x*", true, false)
+            .unwrap()
+            .find("abc")
+            .is_empty());
+        assert!(Query::new("(", true, false).is_err());
+    }
+
+    #[test]
+    fn tree() {
+        let mut project = Project::new(PathBuf::new(), 0, "root".to_string(), false);
+        project
+            .add(String::from("docs: write docs"), false)
+            .unwrap();
+        project.add(String::from("release"), true).unwrap();
+
+        let mut child = Project::new(PathBuf::new(), 1, "child".to_string(), true);
+        child
+            .add(String::from("fix docs, then more Docs"), true)
+            .unwrap();
+        project.attach_child(child);
+
+        let query = Query::new("docs", false, true).unwrap();
+        let found = search(&project, &query);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].project, found[0].task.index), ("root", 0));
+        assert_eq!(found[0].ranges, [0..4, 12..16]);
+        assert_eq!((found[1].project, found[1].task.index), ("child", 0));
+        assert_eq!(found[1].ranges, [4..8, 20..24]);
+    }
+}
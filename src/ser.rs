@@ -1,20 +1,64 @@
+use chrono::{NaiveDate, SecondsFormat};
 use serde::{ser::SerializeStruct, Serialize};
 
-use super::{project::ProjectData, Task};
+use super::{
+    archive::ArchivedTask,
+    hooks::{Event, Hooks},
+    project::ProjectData,
+    scan::CodeMarker,
+    stats::{Counts, ProjectStats, Stats, Week},
+    timer::ActiveTimer,
+    Task,
+};
 
 impl Serialize for ProjectData {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("ProjectData", 3)?;
+        let mut state = serializer.serialize_struct("ProjectData", 5)?;
         state.serialize_field("name", &self.name)?;
-        state.serialize_field("tasks", &self.tasks)?;
+        // toml requires plain values to come before arrays of tables
         if self.is_child {
             state.serialize_field("is_child", &self.is_child)?;
         } else {
             state.skip_field("is_child")?;
         }
+        state.serialize_field("tasks", &self.tasks)?;
+        if self.hooks.is_empty() {
+            state.skip_field("hooks")?;
+        } else {
+            state.serialize_field("hooks", &self.hooks)?;
+        }
+        if self.code.is_empty() {
+            state.skip_field("code")?;
+        } else {
+            state.serialize_field("code", &self.code)?;
+        }
+        state.end()
+    }
+}
+
+impl Serialize for Hooks {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Hooks", 5)?;
+        for event in [
+            Event::PreSave,
+            Event::Add,
+            Event::Complete,
+            Event::Remove,
+            Event::Edit,
+        ] {
+            let commands = self.commands(event);
+            match commands {
+                [] => state.skip_field(event.name())?,
+                [command] => state.serialize_field(event.name(), command)?,
+                _ => state.serialize_field(event.name(), commands)?,
+            }
+        }
         state.end()
     }
 }
@@ -24,10 +68,187 @@ impl Serialize for Task {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("Task", 3)?;
+        let mut state = serializer.serialize_struct("Task", 11)?;
         state.serialize_field("desc", &self.desc)?;
         state.serialize_field("completed", &self.completed)?;
         state.serialize_field("index", &self.index)?;
+        state.serialize_field("id", &self.id)?;
+
+        if let Some(created_at) = self.created_at {
+            state.serialize_field(
+                "created_at",
+                &created_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            )?;
+        } else {
+            state.skip_field("created_at")?;
+        }
+
+        if let Some(completed_at) = self.completed_at {
+            state.serialize_field(
+                "completed_at",
+                &completed_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            )?;
+        } else {
+            state.skip_field("completed_at")?;
+        }
+
+        if let Some(due) = self.due {
+            state.serialize_field("due", &due.to_string())?;
+        } else {
+            state.skip_field("due")?;
+        }
+
+        if self.tags.is_empty() {
+            state.skip_field("tags")?;
+        } else {
+            state.serialize_field("tags", &self.tags)?;
+        }
+
+        if let Some(priority) = self.priority {
+            state.serialize_field("priority", &priority)?;
+        } else {
+            state.skip_field("priority")?;
+        }
+
+        if let Some(estimate) = self.estimate {
+            state.serialize_field("estimate", &estimate.to_string())?;
+        } else {
+            state.skip_field("estimate")?;
+        }
+
+        if self.time.is_empty() {
+            state.skip_field("time")?;
+        } else {
+            let time: Vec<_> = self.time.iter().map(ToString::to_string).collect();
+            state.serialize_field("time", &time)?;
+        }
+
+        state.end()
+    }
+}
+
+impl Serialize for CodeMarker {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("CodeMarker", 4)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("text", &self.text)?;
+        state.serialize_field("file", &self.file)?;
+        state.serialize_field("line", &self.line)?;
+        state.end()
+    }
+}
+
+impl Serialize for ArchivedTask {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ArchivedTask", 4)?;
+        state.serialize_field("project", &self.project.to_string_lossy())?;
+        state.serialize_field("project_name", &self.project_name)?;
+        state.serialize_field(
+            "archived_at",
+            &self.archived_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )?;
+        // a table, has to come last
+        state.serialize_field("task", &self.task)?;
+        state.end()
+    }
+}
+
+impl Serialize for Stats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let burndown: Vec<_> = self
+            .burndown
+            .iter()
+            .map(|(date, open)| BurndownPoint(*date, *open))
+            .collect();
+
+        let mut state = serializer.serialize_struct("Stats", 4)?;
+        state.serialize_field(
+            "median_time_to_complete_secs",
+            &self.median_time_to_complete.map(|d| d.num_seconds()),
+        )?;
+        state.serialize_field("project", &self.project)?;
+        state.serialize_field("weeks", &self.weeks)?;
+        state.serialize_field("burndown", &burndown)?;
+        state.end()
+    }
+}
+
+impl Serialize for ProjectStats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ProjectStats", 4)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("own", &self.own)?;
+        state.serialize_field("total", &self.total)?;
+        state.serialize_field("children", &self.children)?;
+        state.end()
+    }
+}
+
+impl Serialize for Counts {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Counts", 4)?;
+        state.serialize_field("open", &self.open)?;
+        state.serialize_field("done", &self.done)?;
+        state.serialize_field("archived", &self.archived)?;
+        state.serialize_field("percent_done", &self.percent_done())?;
+        state.end()
+    }
+}
+
+impl Serialize for Week {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Week", 3)?;
+        state.serialize_field("start", &self.start.to_string())?;
+        state.serialize_field("added", &self.added)?;
+        state.serialize_field("completed", &self.completed)?;
+        state.end()
+    }
+}
+
+struct BurndownPoint(NaiveDate, usize);
+
+impl Serialize for BurndownPoint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("BurndownPoint", 2)?;
+        state.serialize_field("date", &self.0.to_string())?;
+        state.serialize_field("open", &self.1)?;
+        state.end()
+    }
+}
+
+impl Serialize for ActiveTimer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ActiveTimer", 3)?;
+        state.serialize_field("project", &self.project.to_string_lossy())?;
+        state.serialize_field("task", &self.task_id)?;
+        state.serialize_field(
+            "started_at",
+            &self.started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )?;
         state.end()
     }
 }
@@ -0,0 +1,365 @@
+//! Statistics about a project tree and its history.
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+
+use crate::{archive::ArchivedTask, Project, Task};
+
+/// How many weeks [`Stats::weeks`] goes back
+pub const WEEKS: usize = 8;
+
+/// How many days [`Stats::burndown`] goes back
+pub const BURNDOWN_DAYS: usize = 28;
+
+/// How many tasks are in which state
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub open: usize,
+    pub done: usize,
+    /// Completed tasks that were moved to the archive
+    pub archived: usize,
+}
+
+impl Counts {
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.open + self.done + self.archived
+    }
+
+    /// Percentage of completed tasks, archived ones included. None if there
+    /// are no tasks at all.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent_done(&self) -> Option<f64> {
+        let total = self.total();
+        (total > 0).then(|| (self.done + self.archived) as f64 * 100.0 / total as f64)
+    }
+
+    const fn add(self, other: Self) -> Self {
+        Self {
+            open: self.open + other.open,
+            done: self.done + other.done,
+            archived: self.archived + other.archived,
+        }
+    }
+}
+
+/// The counts of a project and its children
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectStats {
+    pub name: String,
+    /// Tasks of this project only
+    pub own: Counts,
+    /// Tasks of this project and all of its children
+    pub total: Counts,
+    pub children: Vec<Self>,
+}
+
+/// Activity within one week
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Week {
+    /// The monday the week starts on
+    pub start: NaiveDate,
+    pub added: usize,
+    pub completed: usize,
+}
+
+/// Statistics about a project tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub project: ProjectStats,
+    /// The last [`WEEKS`] weeks, oldest first
+    pub weeks: Vec<Week>,
+    /// Median time between adding and completing a task. Only tasks that
+    /// know both are taken into account.
+    pub median_time_to_complete: Option<Duration>,
+    /// Open tasks at the end of each of the last [`BURNDOWN_DAYS`] days,
+    /// oldest first
+    pub burndown: Vec<(NaiveDate, usize)>,
+}
+
+/// Collects statistics about `project` and all of its children. `archived`
+/// may contain tasks of other projects, only those archived from a project
+/// of the tree are taken into account.
+///
+/// Days and weeks are counted in the time zone of `now`.
+#[must_use]
+pub fn collect<Tz: TimeZone>(
+    project: &Project,
+    archived: &[ArchivedTask],
+    now: &DateTime<Tz>,
+) -> Stats {
+    let mut tasks: Vec<&Task> = Vec::new();
+    let project_stats = project_stats(project, archived, &mut tasks);
+    let tz = now.timezone();
+    let today = now.date_naive();
+
+    Stats {
+        project: project_stats,
+        weeks: weeks(&tasks, &tz, today),
+        median_time_to_complete: median_time_to_complete(&tasks),
+        burndown: burndown(&tasks, &tz, today),
+    }
+}
+
+/// Also collects every task of the tree into `tasks`
+fn project_stats<'a>(
+    project: &'a Project,
+    archived: &'a [ArchivedTask],
+    tasks: &mut Vec<&'a Task>,
+) -> ProjectStats {
+    let mut own = Counts::default();
+
+    for task in &project.data.tasks {
+        if task.completed {
+            own.done += 1;
+        } else {
+            own.open += 1;
+        }
+        tasks.push(task);
+    }
+
+    for entry in archived.iter().filter(|e| e.project == project.path()) {
+        own.archived += 1;
+        tasks.push(&entry.task);
+    }
+
+    let children: Vec<_> = project
+        .get_children()
+        .iter()
+        .map(|child| project_stats(child, archived, tasks))
+        .collect();
+
+    let total = children.iter().fold(own, |acc, c| acc.add(c.total));
+
+    ProjectStats {
+        name: project.data.name.clone(),
+        own,
+        total,
+        children,
+    }
+}
+
+fn local_date<Tz: TimeZone>(time: DateTime<Utc>, tz: &Tz) -> NaiveDate {
+    time.with_timezone(tz).date_naive()
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+fn weeks<Tz: TimeZone>(tasks: &[&Task], tz: &Tz, today: NaiveDate) -> Vec<Week> {
+    let current = week_start(today);
+    let mut weeks: Vec<_> = (0..WEEKS)
+        .rev()
+        .map(|i| Week {
+            start: current - Duration::weeks(i as i64),
+            added: 0,
+            completed: 0,
+        })
+        .collect();
+
+    let week_of = |time: Option<DateTime<Utc>>| {
+        let start = week_start(local_date(time?, tz));
+        weeks.iter().position(|w| w.start == start)
+    };
+
+    let mut added = [0; WEEKS];
+    let mut completed = [0; WEEKS];
+    for task in tasks {
+        if let Some(i) = week_of(task.created_at) {
+            added[i] += 1;
+        }
+        if let Some(i) = week_of(task.completed_at) {
+            completed[i] += 1;
+        }
+    }
+
+    for (i, week) in weeks.iter_mut().enumerate() {
+        week.added = added[i];
+        week.completed = completed[i];
+    }
+
+    weeks
+}
+
+fn median_time_to_complete(tasks: &[&Task]) -> Option<Duration> {
+    let mut durations: Vec<_> = tasks
+        .iter()
+        .filter_map(|t| Some(t.completed_at? - t.created_at?))
+        .filter(|d| *d >= Duration::zero())
+        .collect();
+
+    if durations.is_empty() {
+        return None;
+    }
+
+    durations.sort();
+    let mid = durations.len() / 2;
+    if durations.len() % 2 == 0 {
+        Some((durations[mid - 1] + durations[mid]) / 2)
+    } else {
+        Some(durations[mid])
+    }
+}
+
+fn burndown<Tz: TimeZone>(tasks: &[&Task], tz: &Tz, today: NaiveDate) -> Vec<(NaiveDate, usize)> {
+    (0..BURNDOWN_DAYS)
+        .rev()
+        .map(|i| {
+            let day = today - Duration::days(i as i64);
+            let open = tasks
+                .iter()
+                .filter(|t| {
+                    // tasks from before creation times were recorded have always been there
+                    let created = t.created_at.is_none_or(|c| local_date(c, tz) <= day);
+                    let still_open = t
+                        .completed_at
+                        .map_or(!t.completed, |c| local_date(c, tz) > day);
+                    created && still_open
+                })
+                .count();
+
+            (day, open)
+        })
+        .collect()
+}
+
+/// Draws a burndown chart `height` rows high, one column per day
+#[must_use]
+pub fn burndown_chart(burndown: &[(NaiveDate, usize)], height: usize) -> String {
+    let max = burndown.iter().map(|(_, open)| *open).max().unwrap_or(0);
+    let width = max.to_string().len();
+    let mut chart = String::new();
+
+    if max == 0 || height == 0 {
+        return chart;
+    }
+
+    for row in (1..=height).rev() {
+        // the number of open tasks this row stands for
+        let threshold = (max * row).div_ceil(height);
+
+        let label = if row == height || row == 1 {
+            threshold.to_string()
+        } else {
+            String::new()
+        };
+        let _ = write!(chart, "{label:>width$} |");
+
+        for (_, open) in burndown {
+            chart.push(if *open >= threshold { '#' } else { ' ' });
+        }
+        chart.push('\n');
+    }
+
+    let _ = writeln!(chart, "{:>width$} +{}", "", "-".repeat(burndown.len()));
+    if let (Some((first, _)), Some((last, _))) = (burndown.first(), burndown.last()) {
+        let first = first.format("%m-%d").to_string();
+        let last = last.format("%m-%d").to_string();
+        if burndown.len() > first.len() + last.len() {
+            let gap = burndown.len() - first.len() - last.len();
+            let _ = writeln!(chart, "{:>width$}  {first}{}{last}", "", " ".repeat(gap));
+        } else {
+            let _ = writeln!(chart, "{:>width$}  {first}", "");
+        }
+    }
+
+    chart
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{burndown_chart, collect, Counts};
+    use crate::{archive::ArchivedTask, Project, Task};
+
+    fn task(desc: &str, created: u32, completed: Option<u32>) -> Task {
+        let mut task = Task::new(desc, completed.is_some(), 0);
+        task.created_at = Some(Utc.with_ymd_and_hms(2026, 10, created, 12, 0, 0).unwrap());
+        task.completed_at =
+            completed.map(|day| Utc.with_ymd_and_hms(2026, 10, day, 12, 0, 0).unwrap());
+        task
+    }
+
+    #[test]
+    fn collect_stats() {
+        let mut project = Project::new(PathBuf::from("/p/.tutel.toml"), 0, "p".to_string(), false);
+        project.data.tasks.push(task("open", 5, None));
+        project.data.tasks.push(task("done", 12, Some(14)));
+
+        let mut child = Project::new(PathBuf::from("/p/c/.tutel.toml"), 1, "c".to_string(), true);
+        child.data.tasks.push(task("child", 13, None));
+        project.attach_child(child);
+
+        let archived = vec![ArchivedTask {
+            project: PathBuf::from("/p/c/.tutel.toml"),
+            project_name: String::from("c"),
+            archived_at: Utc::now(),
+            task: task("archived", 1, Some(5)),
+        }];
+
+        // a sunday
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let stats = collect(&project, &archived, &now);
+
+        assert_eq!(
+            stats.project.own,
+            Counts {
+                open: 1,
+                done: 1,
+                archived: 0
+            }
+        );
+        assert_eq!(
+            stats.project.total,
+            Counts {
+                open: 2,
+                done: 1,
+                archived: 1
+            }
+        );
+        assert_eq!(stats.project.total.percent_done(), Some(50.0));
+        assert_eq!(Counts::default().percent_done(), None);
+
+        let last = stats.weeks.last().unwrap();
+        assert_eq!(last.start, NaiveDate::from_ymd_opt(2026, 10, 12).unwrap());
+        assert_eq!((last.added, last.completed), (2, 1));
+
+        // 2 and 4 days
+        assert_eq!(stats.median_time_to_complete, Some(Duration::days(3)));
+
+        let open_on = |day| {
+            let date = NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+            stats.burndown.iter().find(|(d, _)| *d == date).unwrap().1
+        };
+        assert_eq!(open_on(1), 1);
+        assert_eq!(open_on(5), 1);
+        assert_eq!(open_on(13), 3);
+        assert_eq!(open_on(14), 2);
+
+        // noon UTC is already the next day at UTC+14
+        let offset = FixedOffset::east_opt(14 * 3600).unwrap();
+        let stats = collect(&project, &archived, &now.with_timezone(&offset));
+        let open_on = |day| {
+            let date = NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+            stats.burndown.iter().find(|(d, _)| *d == date).unwrap().1
+        };
+        assert_eq!(open_on(13), 2);
+        assert_eq!(open_on(14), 3);
+        assert_eq!(open_on(15), 2);
+    }
+
+    #[test]
+    fn chart() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let chart = burndown_chart(&[(day(1), 4), (day(2), 2), (day(3), 1)], 2);
+
+        assert_eq!(chart, "4 |#  \n2 |## \n  +---\n   10-01\n");
+        assert_eq!(burndown_chart(&[(day(1), 0)], 2), "");
+    }
+}
@@ -0,0 +1,244 @@
+//! Where project files are read from and written to.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    fmt, fs,
+    hash::{Hash, Hasher},
+    path::{Component, Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{
+    error::{Error, Result},
+    ProjectData,
+};
+
+/// Identifies a version of a stored project. Two revisions are only ever
+/// compared for equality, to find out whether a project was changed by
+/// someone else.
+pub type Revision = u64;
+
+/// A place to keep projects. Projects are addressed by the path of their
+/// project file, the directories above it make up the project tree.
+pub trait Storage: fmt::Debug + Send + Sync {
+    /// Whether a project is stored at `path`
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Reads the project stored at `path` together with its current revision
+    ///
+    /// # Errors
+    /// This function will return [`Error::NotAProject`] if nothing is stored
+    /// at `path`, or another error if the project could not be read.
+    fn read(&self, path: &Path) -> Result<(ProjectData, Revision)>;
+
+    /// The current revision of the project stored at `path`
+    fn revision(&self, path: &Path) -> Option<Revision>;
+
+    /// Stores `data` at `path` and returns the new revision
+    ///
+    /// # Errors
+    /// This function will return an error if the project could not be
+    /// serialized or written.
+    fn write(&self, path: &Path, data: &ProjectData) -> Result<Revision>;
+
+    /// The directories directly below `dir` that may contain child projects,
+    /// sorted by name
+    fn subdirs(&self, dir: &Path) -> Vec<PathBuf>;
+}
+
+/// Keeps every project as a TOML file in the file system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileStorage;
+
+impl Storage for FileStorage {
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read(&self, path: &Path) -> Result<(ProjectData, Revision)> {
+        if !path.is_file() {
+            return Err(Error::NotAProject(path.to_path_buf()));
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let data = toml::from_str(&content).map_err(|e| Error::parse(path, &e))?;
+
+        Ok((data, hash(&content)))
+    }
+
+    fn revision(&self, path: &Path) -> Option<Revision> {
+        fs::read_to_string(path).ok().map(|c| hash(&c))
+    }
+
+    fn write(&self, path: &Path, data: &ProjectData) -> Result<Revision> {
+        let serialized = toml::to_string_pretty(data)?;
+        fs::write(path, &serialized).map_err(|e| Error::io(path, e))?;
+
+        Ok(hash(&serialized))
+    }
+
+    fn subdirs(&self, dir: &Path) -> Vec<PathBuf> {
+        let Ok(iter) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        // read_dir makes no guarantees about ordering
+        let mut dirs: Vec<_> = iter
+            .filter_map(std::result::Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        dirs.sort();
+        dirs
+    }
+}
+
+fn hash(content: &str) -> Revision {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Keeps projects in memory. Useful for tests and for embedding tutel
+/// somewhere without a file system.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    projects: Mutex<MemoryProjects>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryProjects {
+    stored: BTreeMap<PathBuf, (ProjectData, Revision)>,
+    last_revision: Revision,
+}
+
+impl MemoryStorage {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn projects(&self) -> std::sync::MutexGuard<'_, MemoryProjects> {
+        // the map is never left in an inconsistent state
+        self.projects.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn exists(&self, path: &Path) -> bool {
+        self.projects().stored.contains_key(path)
+    }
+
+    fn read(&self, path: &Path) -> Result<(ProjectData, Revision)> {
+        self.projects()
+            .stored
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::NotAProject(path.to_path_buf()))
+    }
+
+    fn revision(&self, path: &Path) -> Option<Revision> {
+        self.projects().stored.get(path).map(|(_, r)| *r)
+    }
+
+    fn write(&self, path: &Path, data: &ProjectData) -> Result<Revision> {
+        let mut projects = self.projects();
+        projects.last_revision += 1;
+        let revision = projects.last_revision;
+        projects
+            .stored
+            .insert(path.to_path_buf(), (data.clone(), revision));
+        drop(projects);
+
+        Ok(revision)
+    }
+
+    fn subdirs(&self, dir: &Path) -> Vec<PathBuf> {
+        let dirs: BTreeSet<_> = self
+            .projects()
+            .stored
+            .keys()
+            .filter_map(|path| {
+                let mut components = path.strip_prefix(dir).ok()?.components();
+                let first = components.next()?;
+                // the project file itself is not a directory
+                components.next()?;
+                matches!(first, Component::Normal(_)).then(|| dir.join(first))
+            })
+            .collect();
+
+        dirs.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::{path::Path, sync::Arc};
+
+    use super::{MemoryStorage, Storage};
+    use crate::{load_project_rec_from, Error, Project, PROJECT_FILE_NAME};
+
+    fn store(storage: &MemoryStorage, dir: &str, name: &str, is_child: bool) {
+        let mut project = Project::new(
+            Path::new(dir).join(PROJECT_FILE_NAME),
+            0,
+            name.to_string(),
+            is_child,
+        );
+        project.add(format!("task of {name}"), false).unwrap();
+        storage
+            .write(project.path(), &project.data)
+            .expect("unable to store project");
+    }
+
+    #[test]
+    fn project_tree_in_memory() {
+        let storage = MemoryStorage::new();
+        store(&storage, "/root", "root", false);
+        store(&storage, "/root/b/nested", "nested", true);
+        store(&storage, "/root/a", "a", true);
+        store(&storage, "/elsewhere", "elsewhere", false);
+
+        assert_eq!(
+            storage.subdirs(Path::new("/root")),
+            vec![Path::new("/root/a"), Path::new("/root/b")]
+        );
+
+        let storage: Arc<dyn Storage> = Arc::new(storage);
+        let project = load_project_rec_from(&storage, Path::new("/root/b")).unwrap();
+
+        assert_eq!(project.data.name, "root");
+        assert_eq!(project.steps(), -1);
+        let children: Vec<_> = project
+            .get_children()
+            .iter()
+            .map(|c| c.data.name.as_str())
+            .collect();
+        assert_eq!(children, vec!["a", "nested"]);
+
+        assert!(matches!(
+            load_project_rec_from(&storage, Path::new("/nothing/here")),
+            Err(Error::NoProject(_))
+        ));
+    }
+
+    #[test]
+    fn lock_conflict_in_memory() {
+        let storage = Arc::new(MemoryStorage::new());
+        store(&storage, "/p", "p", false);
+        let path = Path::new("/p").join(PROJECT_FILE_NAME);
+
+        let mut first = Project::load_from(storage.clone(), path.clone(), 0).unwrap();
+        let mut second = Project::load_from(storage.clone(), path.clone(), 0).unwrap();
+
+        first.add(String::from("first"), false).unwrap();
+        first.save().unwrap();
+
+        second.add(String::from("second"), false).unwrap();
+        assert!(matches!(second.save(), Err(Error::LockConflict(_))));
+
+        let (data, _) = storage.read(&path).unwrap();
+        assert_eq!(data.tasks.len(), 2);
+    }
+}
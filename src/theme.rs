@@ -0,0 +1,344 @@
+use std::{env, ffi::OsString, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use owo_colors::{AnsiColors, DynColors, OwoColorize, Style};
+use toml::value::Table;
+
+/// When to emit ANSI color codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Decides whether colors should be used for a stream.
+    ///
+    /// `Auto` honors `NO_COLOR` and `CLICOLOR_FORCE` (in that order) and
+    /// otherwise only colors terminals.
+    pub fn enabled(self, is_tty: bool) -> bool {
+        self.resolve(
+            env::var_os("NO_COLOR"),
+            env::var_os("CLICOLOR_FORCE"),
+            is_tty,
+        )
+    }
+
+    fn resolve(
+        self,
+        no_color: Option<OsString>,
+        clicolor_force: Option<OsString>,
+        is_tty: bool,
+    ) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                if no_color.is_some_and(|v| !v.is_empty()) {
+                    false
+                } else if clicolor_force.is_some_and(|v| !v.is_empty() && v != "0") {
+                    true
+                } else {
+                    is_tty
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("expected 'auto', 'always' or 'never', got '{s}'")),
+        }
+    }
+}
+
+/// The symbols used to draw the todo list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyphs {
+    pub done: String,
+    pub open: String,
+    pub separator: String,
+    pub empty: String,
+}
+
+impl Glyphs {
+    fn unicode() -> Self {
+        Self {
+            done: String::from("✓"),
+            open: String::from("X"),
+            separator: String::from("│"),
+            empty: String::from("[empty]"),
+        }
+    }
+
+    fn ascii() -> Self {
+        Self {
+            done: String::from("x"),
+            open: String::from(" "),
+            separator: String::from("|"),
+            empty: String::from("[empty]"),
+        }
+    }
+}
+
+/// The colors used to draw the todo list
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub done: DynColors,
+    pub open: DynColors,
+    pub bracket: DynColors,
+    pub steps: DynColors,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            done: DynColors::Ansi(AnsiColors::Green),
+            open: DynColors::Ansi(AnsiColors::Red),
+            bracket: DynColors::Ansi(AnsiColors::Yellow),
+            steps: DynColors::Ansi(AnsiColors::Blue),
+        }
+    }
+}
+
+/// Determines how the todo list is rendered
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub color: bool,
+    pub glyphs: Glyphs,
+    pub palette: Palette,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            color: true,
+            glyphs: Glyphs::unicode(),
+            palette: Palette::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// Builds a theme from the `[theme]` section of the user config.
+    ///
+    /// ```toml
+    /// [theme]
+    /// ascii = false
+    /// color = "auto"
+    ///
+    /// [theme.glyphs]
+    /// done = "✓"
+    /// open = "X"
+    /// separator = "│"
+    /// empty = "[empty]"
+    ///
+    /// [theme.palette]
+    /// done = "green"
+    /// open = "red"
+    /// bracket = "yellow"
+    /// steps = "#0000ff"
+    /// ```
+    ///
+    /// `color` is only used when `cli_color` is `None`, `ascii` is forced on
+    /// when `cli_ascii` is set.
+    pub fn from_config(
+        section: Option<&Table>,
+        cli_color: Option<ColorChoice>,
+        cli_ascii: bool,
+        is_tty: bool,
+    ) -> Result<Self> {
+        let empty = Table::new();
+        let section = section.unwrap_or(&empty);
+
+        let ascii = cli_ascii || get_bool(section, "ascii")?.unwrap_or(false);
+        let color = match cli_color {
+            Some(color) => color,
+            None => match get_str(section, "color")? {
+                Some(color) => color.parse().map_err(|e| anyhow!("theme.color: {e}"))?,
+                None => ColorChoice::Auto,
+            },
+        };
+
+        let mut glyphs = if ascii {
+            Glyphs::ascii()
+        } else {
+            Glyphs::unicode()
+        };
+
+        if let Some(table) = get_table(section, "glyphs")? {
+            for (target, key) in [
+                (&mut glyphs.done, "done"),
+                (&mut glyphs.open, "open"),
+                (&mut glyphs.separator, "separator"),
+                (&mut glyphs.empty, "empty"),
+            ] {
+                if let Some(value) = get_str(table, key)? {
+                    *target = value.to_string();
+                }
+            }
+        }
+
+        let mut palette = Palette::default();
+        if let Some(table) = get_table(section, "palette")? {
+            for (target, key) in [
+                (&mut palette.done, "done"),
+                (&mut palette.open, "open"),
+                (&mut palette.bracket, "bracket"),
+                (&mut palette.steps, "steps"),
+            ] {
+                if let Some(value) = get_str(table, key)? {
+                    *target = value
+                        .parse()
+                        .map_err(|_| anyhow!("theme.palette.{key}: invalid color '{value}'"))?;
+                }
+            }
+        }
+
+        Ok(Self {
+            color: color.enabled(is_tty),
+            glyphs,
+            palette,
+        })
+    }
+
+    /// Applies `style` to `text`, unless colors are disabled
+    pub fn paint(&self, text: &str, style: Style) -> String {
+        if self.color {
+            text.style(style).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn done(&self, text: &str) -> String {
+        self.paint(text, Style::new().color(self.palette.done))
+    }
+
+    pub fn open(&self, text: &str) -> String {
+        self.paint(text, Style::new().color(self.palette.open))
+    }
+
+    pub fn bracket(&self, text: &str) -> String {
+        self.paint(text, Style::new().color(self.palette.bracket).bold())
+    }
+
+    pub fn steps(&self, text: &str) -> String {
+        self.paint(text, Style::new().color(self.palette.steps).bold())
+    }
+
+    pub fn bold(&self, text: &str) -> String {
+        self.paint(text, Style::new().bold())
+    }
+
+    pub fn dim(&self, text: &str) -> String {
+        self.paint(text, Style::new().dimmed())
+    }
+
+    /// Text that matched a search
+    pub fn highlight(&self, text: &str) -> String {
+        self.paint(text, Style::new().bold().underline())
+    }
+
+    /// The checkbox in front of a task or project
+    pub fn marker(&self, completed: bool) -> String {
+        if completed {
+            self.done(&format!("[{}]", self.glyphs.done))
+        } else {
+            self.open(&format!("[{}]", self.glyphs.open))
+        }
+    }
+}
+
+fn get_str<'a>(table: &'a Table, key: &str) -> Result<Option<&'a str>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::String(s)) => Ok(Some(s.as_str())),
+        Some(_) => bail!("{key} must be a string"),
+    }
+}
+
+fn get_bool(table: &Table, key: &str) -> Result<Option<bool>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::Boolean(b)) => Ok(Some(*b)),
+        Some(_) => bail!("{key} must be a boolean"),
+    }
+}
+
+fn get_table<'a>(table: &'a Table, key: &str) -> Result<Option<&'a Table>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::Table(t)) => Ok(Some(t)),
+        Some(_) => bail!("{key} must be a table"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::{ColorChoice, Theme};
+
+    #[test]
+    fn color_choice() {
+        let set = || Some(OsString::from("1"));
+
+        assert!(ColorChoice::Always.resolve(set(), None, false));
+        assert!(!ColorChoice::Never.resolve(None, set(), true));
+
+        assert!(ColorChoice::Auto.resolve(None, None, true));
+        assert!(!ColorChoice::Auto.resolve(None, None, false));
+        assert!(!ColorChoice::Auto.resolve(set(), set(), true));
+        assert!(ColorChoice::Auto.resolve(None, set(), false));
+        assert!(!ColorChoice::Auto.resolve(None, Some(OsString::from("0")), false));
+    }
+
+    #[test]
+    fn theme_from_config() {
+        let config: toml::value::Table = toml::from_str(
+            r#"
+            ascii = true
+            color = "never"
+
+            [glyphs]
+            done = "+"
+
+            [palette]
+            open = "bright red"
+            "#,
+        )
+        .unwrap();
+
+        let theme = Theme::from_config(Some(&config), None, false, true).unwrap();
+
+        assert!(!theme.color);
+        assert_eq!(theme.glyphs.done, "+");
+        assert_eq!(theme.glyphs.separator, "|");
+        assert_eq!(theme.marker(false), "[ ]");
+
+        let theme = Theme::from_config(Some(&config), Some(ColorChoice::Always), false, false);
+        assert!(theme.unwrap().color);
+    }
+
+    #[test]
+    fn invalid_palette() {
+        let config: toml::value::Table = toml::from_str(
+            r#"
+            [palette]
+            done = "not a color"
+            "#,
+        )
+        .unwrap();
+
+        assert!(Theme::from_config(Some(&config), None, false, false).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Time tracking. Work on a task is recorded as intervals on the task, the
+//! currently running timer is kept outside of the project.
+
+use std::{
+    fmt::{self, Write},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+
+use crate::{
+    error::{Error, Result},
+    Project, Task,
+};
+
+/// A span of time spent working on a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Interval {
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// The part of this interval that lies between `since` and `until`
+    #[must_use]
+    pub fn clipped(&self, since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> Duration {
+        let start = since.map_or(self.start, |since| self.start.max(since));
+        let end = until.map_or(self.end, |until| self.end.min(until));
+
+        (end - start).max(Duration::zero())
+    }
+}
+
+/// Formats as an ISO 8601 interval, e.g.
+/// `2026-10-18T10:00:00Z/2026-10-18T11:30:00Z`
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}",
+            self.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+            self.end.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |v: &str| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|d| d.with_timezone(&Utc))
+                .map_err(|e| format!("invalid interval {s}: {e}"))
+        };
+
+        let (start, end) = s
+            .split_once('/')
+            .ok_or_else(|| format!("invalid interval {s}: expected START/END"))?;
+
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+impl Task {
+    /// The time tracked on this task
+    #[must_use]
+    pub fn tracked(&self) -> Duration {
+        self.time
+            .iter()
+            .fold(Duration::zero(), |acc, i| acc + i.duration())
+    }
+}
+
+/// The timer that is currently running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveTimer {
+    /// Path of the project file the task belongs to
+    pub project: PathBuf,
+    pub task_id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl ActiveTimer {
+    /// Reads the running timer from the state file at `path`
+    ///
+    /// # Errors
+    /// This function will return an error if the state file exists but
+    /// could not be read or parsed.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let timer = toml::from_str(&content).map_err(|e| Error::parse(path, &e))?;
+
+        Ok(Some(timer))
+    }
+
+    /// Writes this timer to the state file at `path`
+    ///
+    /// # Errors
+    /// This function will return an error if the state file could not be
+    /// written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+
+        let serialized = toml::to_string(self)?;
+        fs::write(path, serialized).map_err(|e| Error::io(path, e))
+    }
+
+    /// Removes the state file at `path`, there is no running timer afterwards
+    ///
+    /// # Errors
+    /// This function will return an error if the state file exists but could
+    /// not be removed.
+    pub fn clear(path: &Path) -> Result<()> {
+        if path.is_file() {
+            fs::remove_file(path).map_err(|e| Error::io(path, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops the timer at `end` and records the interval on its task, which
+    /// is searched in `project` and all of its children. Returns the task.
+    ///
+    /// # Errors
+    /// This function will return an error if the task could not be found.
+    pub fn stop<'a>(&self, project: &'a mut Project, end: DateTime<Utc>) -> Result<&'a Task> {
+        let name = project.data.name.clone();
+        let task = find_by_id(project, &self.task_id).ok_or_else(|| Error::TaskNotFound {
+            project: name,
+            task: self.task_id.clone(),
+        })?;
+
+        task.time.push(Interval {
+            start: self.started_at,
+            end: end.max(self.started_at),
+        });
+
+        Ok(task)
+    }
+}
+
+fn find_by_id<'a>(project: &'a mut Project, id: &str) -> Option<&'a mut Task> {
+    if let Some(i) = project.data.tasks.iter().position(|t| t.id == id) {
+        return Some(&mut project.data.tasks[i]);
+    }
+
+    project
+        .get_children_mut()
+        .iter_mut()
+        .find_map(|child| find_by_id(child, id))
+}
+
+/// Time spent on one task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportLine<'a> {
+    pub project: &'a str,
+    pub task: &'a Task,
+    pub time: Duration,
+}
+
+/// Sums up the time tracked between `since` and `until` for every task of
+/// `project` and all of its children. Tasks without any time are left out.
+#[must_use]
+pub fn report(
+    project: &Project,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Vec<ReportLine<'_>> {
+    let mut lines = Vec::new();
+    report_rec(project, since, until, &mut lines);
+    lines
+}
+
+fn report_rec<'a>(
+    project: &'a Project,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    lines: &mut Vec<ReportLine<'a>>,
+) {
+    for task in &project.data.tasks {
+        let time = task
+            .time
+            .iter()
+            .fold(Duration::zero(), |acc, i| acc + i.clipped(since, until));
+
+        if time > Duration::zero() {
+            lines.push(ReportLine {
+                project: &project.data.name,
+                task,
+                time,
+            });
+        }
+    }
+
+    for child in project.get_children() {
+        report_rec(child, since, until, lines);
+    }
+}
+
+/// Renders a report as CSV with a header line
+#[must_use]
+pub fn render_csv(lines: &[ReportLine]) -> String {
+    let mut out = String::from("project,index,id,task,seconds\n");
+
+    for line in lines {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{}",
+            csv_field(line.project),
+            line.task.index,
+            line.task.id,
+            csv_field(&line.task.desc),
+            line.time.num_seconds()
+        );
+    }
+
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use super::{render_csv, report, ActiveTimer, Interval};
+    use crate::{Project, PROJECT_FILE_NAME};
+
+    #[test]
+    fn interval_roundtrip() {
+        let interval: Interval = "2026-10-18T10:00:00Z/2026-10-18T11:30:00Z".parse().unwrap();
+        assert_eq!(interval.duration(), Duration::minutes(90));
+        assert_eq!(
+            interval.to_string(),
+            "2026-10-18T10:00:00Z/2026-10-18T11:30:00Z"
+        );
+
+        let since = Utc.with_ymd_and_hms(2026, 10, 18, 11, 0, 0).unwrap();
+        assert_eq!(interval.clipped(Some(since), None), Duration::minutes(30));
+        assert_eq!(interval.clipped(None, Some(since)), Duration::minutes(60));
+
+        assert!("2026-10-18T10:00:00Z".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn start_stop_report() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let state = tmpdir.path().join("state/timer.toml");
+        assert_eq!(ActiveTimer::load(&state).unwrap(), None);
+
+        let mut project = Project::new(PathBuf::from(PROJECT_FILE_NAME), 0, "p".to_string(), false);
+        let mut child = Project::new(PathBuf::new(), 1, "c".to_string(), true);
+        let id = child
+            .add(String::from("work, hard"), false)
+            .unwrap()
+            .id
+            .clone();
+        project.attach_child(child);
+
+        let start = Utc.with_ymd_and_hms(2026, 10, 18, 10, 0, 0).unwrap();
+        let timer = ActiveTimer {
+            project: PathBuf::from(PROJECT_FILE_NAME),
+            task_id: id.clone(),
+            started_at: start,
+        };
+        timer.save(&state).unwrap();
+        assert_eq!(ActiveTimer::load(&state).unwrap(), Some(timer.clone()));
+
+        let task = timer
+            .stop(&mut project, start + Duration::hours(2))
+            .unwrap();
+        assert_eq!(task.tracked(), Duration::hours(2));
+        ActiveTimer::clear(&state).unwrap();
+        assert_eq!(ActiveTimer::load(&state).unwrap(), None);
+
+        let lines = report(&project, Some(start + Duration::hours(1)), None);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].time, Duration::hours(1));
+        assert_eq!(
+            render_csv(&lines),
+            format!("project,index,id,task,seconds\nc,0,{id},\"work, hard\",3600\n")
+        );
+
+        let gone = ActiveTimer {
+            task_id: String::from("gone"),
+            ..timer
+        };
+        assert!(gone.stop(&mut project, start).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+//! Waiting for changes to the project files of a tree.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{CHILDREN_DEPTH_LIMIT, PROJECT_FILE_NAME};
+
+/// How often project files are checked when polling
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Changes within this time of each other are reported once
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Modification time and size of every project file of a tree
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// Watches the project files in a directory and the sub-directories children
+/// are loaded from, including ones created later on.
+///
+/// Uses inotify on Linux and falls back to polling everywhere else, or when
+/// inotify is not available.
+pub struct Watcher {
+    dir: PathBuf,
+    /// What was last reported
+    snapshot: Snapshot,
+    /// What was last seen while polling
+    polled: Snapshot,
+    #[cfg(target_os = "linux")]
+    inotify: Option<inotify::Inotify>,
+}
+
+impl Watcher {
+    /// Watches `dir`, the directory of a root project
+    #[must_use]
+    pub fn new(dir: &Path) -> Self {
+        let mut watcher = Self::polling(dir);
+
+        #[cfg(target_os = "linux")]
+        {
+            watcher.inotify = inotify::Inotify::new().ok();
+            watcher.rescan();
+        }
+
+        watcher
+    }
+
+    /// Watches `dir` by polling, even if inotify is available
+    #[must_use]
+    pub fn polling(dir: &Path) -> Self {
+        let snapshot = snapshot(dir);
+        Self {
+            dir: dir.to_path_buf(),
+            polled: snapshot.clone(),
+            snapshot,
+            #[cfg(target_os = "linux")]
+            inotify: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_polling(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return self.inotify.is_none();
+        #[cfg(not(target_os = "linux"))]
+        return true;
+    }
+
+    /// Blocks until a project file is created, changed or removed
+    pub fn wait(&mut self) {
+        while !self.wait_timeout(None) {}
+    }
+
+    /// Blocks until a project file is created, changed or removed, or until
+    /// `timeout` has passed. Returns whether something changed.
+    pub fn wait_timeout(&mut self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let remaining = || deadline.map(|d| d.saturating_duration_since(Instant::now()));
+
+        loop {
+            if remaining() == Some(Duration::ZERO) {
+                return false;
+            }
+
+            if !self.next_hint(remaining()) {
+                continue;
+            }
+
+            // wait until things calmed down
+            while self.next_hint(Some(DEBOUNCE)) {}
+
+            self.rescan();
+            self.polled = snapshot(&self.dir);
+            if self.polled != self.snapshot {
+                self.snapshot = self.polled.clone();
+                return true;
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for something that may be a change
+    fn next_hint(&mut self, timeout: Option<Duration>) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &self.inotify {
+            match inotify.wait(timeout) {
+                Ok(hint) => return hint,
+                // e.g. too many watches, keep going without inotify
+                Err(_) => self.inotify = None,
+            }
+        }
+
+        let interval = timeout.map_or(POLL_INTERVAL, |t| t.min(POLL_INTERVAL));
+        thread::sleep(interval);
+        let polled = snapshot(&self.dir);
+        let changed = polled != self.polled;
+        self.polled = polled;
+        changed
+    }
+
+    /// Watches directories that were created since the last scan
+    fn rescan(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &self.inotify {
+            if inotify.watch_all(&directories(&self.dir)).is_err() {
+                self.inotify = None;
+            }
+        }
+    }
+}
+
+/// `dir` and every sub-directory children are loaded from
+fn directories(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![dir.to_path_buf()];
+    let mut level = vec![dir.to_path_buf()];
+
+    for _ in 0..CHILDREN_DEPTH_LIMIT {
+        level = level
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(std::result::Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        dirs.extend(level.iter().cloned());
+    }
+
+    dirs
+}
+
+fn snapshot(dir: &Path) -> Snapshot {
+    directories(dir)
+        .into_iter()
+        .map(|dir| dir.join(PROJECT_FILE_NAME))
+        .filter_map(|file| {
+            let metadata = fs::metadata(&file).ok()?;
+            Some((file, (metadata.modified().ok(), metadata.len())))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::{
+        convert::TryFrom, io, mem::MaybeUninit, os::fd::OwnedFd, path::PathBuf, time::Duration,
+    };
+
+    use rustix::{
+        event::{poll, PollFd, PollFlags, Timespec},
+        fs::inotify::{self, CreateFlags, ReadFlags, WatchFlags},
+        io::Errno,
+    };
+
+    use crate::PROJECT_FILE_NAME;
+
+    pub struct Inotify {
+        fd: OwnedFd,
+    }
+
+    impl Inotify {
+        pub fn new() -> io::Result<Self> {
+            let fd = inotify::init(CreateFlags::CLOEXEC | CreateFlags::NONBLOCK)?;
+            Ok(Self { fd })
+        }
+
+        /// Adding a directory that is already watched does nothing
+        pub fn watch_all(&self, dirs: &[PathBuf]) -> io::Result<()> {
+            let flags = WatchFlags::CREATE
+                | WatchFlags::DELETE
+                | WatchFlags::CLOSE_WRITE
+                | WatchFlags::MOVE
+                | WatchFlags::ONLYDIR;
+
+            for dir in dirs {
+                match inotify::add_watch(&self.fd, dir.as_path(), flags) {
+                    // removed in the meantime
+                    Ok(_) | Err(Errno::NOENT | Errno::NOTDIR) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Waits for events and returns whether one of them concerned a
+        /// project file or a directory
+        pub fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+            let timeout = timeout
+                .map(Timespec::try_from)
+                .transpose()
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+            let mut fds = [PollFd::new(&self.fd, PollFlags::IN)];
+
+            match poll(&mut fds, timeout.as_ref()) {
+                Ok(0) | Err(Errno::INTR) => return Ok(false),
+                Ok(_) => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            let mut buf = [MaybeUninit::uninit(); 4096];
+            let mut reader = inotify::Reader::new(&self.fd, &mut buf);
+            let mut relevant = false;
+            loop {
+                match reader.next() {
+                    Ok(event) => {
+                        let events = event.events();
+                        relevant |= events.contains(ReadFlags::ISDIR)
+                            || events.contains(ReadFlags::QUEUE_OVERFLOW)
+                            || event.file_name().is_some_and(|name| {
+                                name.to_bytes() == PROJECT_FILE_NAME.as_bytes()
+                            });
+                    }
+                    Err(Errno::AGAIN) => return Ok(relevant),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use super::Watcher;
+    use crate::PROJECT_FILE_NAME;
+
+    fn changes(watcher: &mut Watcher) {
+        let timeout = Some(Duration::from_secs(5));
+        let dir = watcher.dir.clone();
+
+        // nothing happened yet
+        assert!(!watcher.wait_timeout(Some(Duration::from_millis(50))));
+
+        fs::write(dir.join(PROJECT_FILE_NAME), "name = 'root'").unwrap();
+        assert!(watcher.wait_timeout(timeout));
+
+        // other files don't matter
+        fs::write(dir.join("notes.txt"), "hi").unwrap();
+        assert!(!watcher.wait_timeout(Some(Duration::from_millis(700))));
+
+        // a child project in a new directory
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("a/b").join(PROJECT_FILE_NAME), "name = 'b'").unwrap();
+        assert!(watcher.wait_timeout(timeout));
+
+        fs::remove_file(dir.join(PROJECT_FILE_NAME)).unwrap();
+        assert!(watcher.wait_timeout(timeout));
+    }
+
+    #[test]
+    fn watch() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let mut watcher = Watcher::new(tmpdir.path());
+        #[cfg(target_os = "linux")]
+        assert!(!watcher.is_polling());
+
+        changes(&mut watcher);
+    }
+
+    #[test]
+    fn poll() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let mut watcher = Watcher::polling(tmpdir.path());
+        assert!(watcher.is_polling());
+
+        changes(&mut watcher);
+    }
+}
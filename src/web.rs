@@ -0,0 +1,554 @@
+//! A small REST API and web page on top of [`rpc::Server`], for looking at
+//! and checking off tasks in a browser on the same machine.
+//!
+//! - `GET /`: the web page
+//! - `GET /api/tree`: the whole tree
+//! - `POST /api/tasks`: adds a task, the body takes the params of `add`
+//! - `PATCH /api/tasks/{task}`: changes `desc` and/or `completed` of a task
+//! - `DELETE /api/tasks/{task}`: removes a task
+//!
+//! `{task}` is an index or an id prefix. The project is given with
+//! `?project=NAME` and defaults to the root project.
+//!
+//! Only requests addressed to a loopback name or the address the server
+//! listens on are answered, and writes need a JSON body. Together, this keeps
+//! other web sites opened in the same browser from using the API.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::{Error, Result},
+    rpc::{self, RpcError, Server},
+};
+
+const INDEX: &str = include_str!("web.html");
+
+/// Larger request bodies are rejected
+const MAX_BODY: usize = 1024 * 1024;
+
+/// Longer request or header lines are rejected
+const MAX_LINE: usize = 8 * 1024;
+
+/// Requests with more headers are rejected
+const MAX_HEADERS: usize = 100;
+
+/// Connections that send nothing for this long are closed
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The parts of an HTTP request the API looks at
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Path and query
+    pub target: String,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &json!({ "error": message }))
+    }
+
+    const fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+impl From<RpcError> for Response {
+    fn from(e: RpcError) -> Self {
+        let status = match e.code {
+            rpc::PARSE_ERROR | rpc::INVALID_REQUEST | rpc::INVALID_PARAMS => 400,
+            rpc::METHOD_NOT_FOUND | rpc::TASK_NOT_FOUND => 404,
+            rpc::CONFLICT => 409,
+            _ => 500,
+        };
+
+        Self::error(status, &e.message)
+    }
+}
+
+/// Binds `addr`, e.g. `127.0.0.1:8080`, for [`serve`]
+///
+/// # Errors
+/// This function will return an error if `addr` is not a loopback address or
+/// could not be bound.
+pub fn listen(addr: &str) -> Result<TcpListener> {
+    let addrs = loopback_addrs(addr)?;
+    TcpListener::bind(addrs.as_slice()).map_err(|e| Error::io(addr, e))
+}
+
+/// Answers requests on `listener` until the process is stopped
+///
+/// # Errors
+/// This function will return an error if the address of `listener` can't
+/// be determined.
+pub fn serve(server: &Arc<Server>, listener: &TcpListener) -> Result<()> {
+    let local = listener
+        .local_addr()
+        .map_err(|e| Error::io("listener", e))?;
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        // a client that never finishes its request would keep its thread forever
+        if stream.set_read_timeout(Some(READ_TIMEOUT)).is_err() {
+            continue;
+        }
+
+        let server = Arc::clone(server);
+        let local_ip = local.ip().to_string();
+        thread::spawn(move || {
+            // the client went away, nothing to answer
+            let _ = connection(&server, &stream, &local_ip);
+        });
+    }
+
+    Ok(())
+}
+
+/// Resolves `addr`, which has to be a loopback address. The API has no
+/// authentication, and checking the Host header doesn't keep other machines
+/// out, they can send any name they like.
+fn loopback_addrs(addr: &str) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<_> = addr
+        .to_socket_addrs()
+        .map_err(|e| Error::io(addr, e))?
+        .collect();
+
+    if addrs.is_empty() || addrs.iter().any(|a| !a.ip().is_loopback()) {
+        return Err(Error::io(
+            addr,
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only loopback addresses like 127.0.0.1 can be listened on",
+            ),
+        ));
+    }
+
+    Ok(addrs)
+}
+
+fn connection(server: &Server, stream: &TcpStream, local_ip: &str) -> io::Result<()> {
+    let response = match read_request(stream, local_ip) {
+        Ok(request) => handle(server, &request),
+        Err(response) => response,
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+fn read_request(stream: impl Read, local_ip: &str) -> std::result::Result<Request, Response> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    read_line(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request"));
+    };
+
+    let mut request = Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        ..Request::default()
+    };
+    let mut host = None;
+    let mut length = 0;
+
+    for headers in 0.. {
+        line.clear();
+        read_line(&mut reader, &mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return Err(Response::error(431, "too many headers"));
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(Response::error(400, "malformed header"));
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "host" => host = Some(value.to_string()),
+            "content-type" => request.content_type = Some(value.to_ascii_lowercase()),
+            "content-length" => {
+                length = value
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid content length"))?;
+            }
+            _ => {}
+        }
+    }
+
+    if !host.is_some_and(|host| is_allowed_host(&host, local_ip)) {
+        return Err(Response::error(403, "unknown host"));
+    }
+    if length > MAX_BODY {
+        return Err(Response::error(413, "request body too large"));
+    }
+
+    request.body = vec![0; length];
+    reader
+        .read_exact(&mut request.body)
+        .map_err(|e| read_error(&e))?;
+
+    Ok(request)
+}
+
+/// Reads a line of at most [`MAX_LINE`] bytes
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> std::result::Result<(), Response> {
+    let read = reader
+        .take(MAX_LINE as u64 + 1)
+        .read_line(line)
+        .map_err(|e| read_error(&e))?;
+
+    if read > MAX_LINE {
+        return Err(Response::error(431, "line too long"));
+    }
+
+    Ok(())
+}
+
+fn read_error(e: &io::Error) -> Response {
+    match e.kind() {
+        // the read timeout of the connection passed
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            Response::error(408, "request timed out")
+        }
+        _ => Response::error(400, "malformed request"),
+    }
+}
+
+/// Whether the Host header names this machine, see the module documentation
+fn is_allowed_host(host: &str, local_ip: &str) -> bool {
+    // [::1]:8080 or localhost:8080
+    let name = host.strip_prefix('[').map_or_else(
+        || host.split(':').next().unwrap_or_default(),
+        |rest| rest.split(']').next().unwrap_or_default(),
+    );
+
+    matches!(name, "localhost" | "127.0.0.1" | "::1") || name == local_ip
+}
+
+/// Answers a single request
+#[must_use]
+pub fn handle(server: &Server, request: &Request) -> Response {
+    let (path, query) = request
+        .target
+        .split_once('?')
+        .unwrap_or((request.target.as_str(), ""));
+
+    let mut params = Map::new();
+    if let Some(project) = query_param(query, "project") {
+        params.insert(String::from("project"), Value::String(project));
+    }
+
+    let task = path.strip_prefix("/api/tasks/").filter(|t| !t.is_empty());
+    let result = match (request.method.as_str(), path, task) {
+        ("GET", "/", _) => {
+            return Response {
+                status: 200,
+                content_type: "text/html",
+                body: String::from(INDEX),
+            }
+        }
+        ("GET", "/api/tree", _) => server.call("list", &params).map(|tree| (200, tree)),
+        ("POST", "/api/tasks", _) => json_body(request).and_then(|body| {
+            params.extend(body);
+            server.call("add", &params).map(|task| (201, task))
+        }),
+        ("PATCH", _, Some(task)) => json_body(request).and_then(|body| {
+            params.insert(String::from("task"), Value::String(percent_decode(task)));
+            patch(server, params, &body).map(|task| (200, task))
+        }),
+        ("DELETE", _, Some(task)) => {
+            params.insert(String::from("task"), Value::String(percent_decode(task)));
+            server.call("remove", &params).map(|task| (200, task))
+        }
+        (_, "/" | "/api/tree" | "/api/tasks", _) | (_, _, Some(_)) => {
+            return Response::error(405, "method not allowed")
+        }
+        _ => return Response::error(404, "not found"),
+    };
+
+    match result {
+        Ok((status, body)) => Response::json(status, &body),
+        Err(e) => e.into(),
+    }
+}
+
+fn json_body(request: &Request) -> std::result::Result<Map<String, Value>, RpcError> {
+    let is_json = request
+        .content_type
+        .as_deref()
+        .is_some_and(|t| t.starts_with("application/json"));
+    if !is_json {
+        return Err(RpcError {
+            code: rpc::INVALID_REQUEST,
+            message: String::from("expected a JSON body"),
+        });
+    }
+
+    match serde_json::from_slice(&request.body) {
+        Ok(Value::Object(body)) => Ok(body),
+        _ => Err(RpcError {
+            code: rpc::PARSE_ERROR,
+            message: String::from("the body has to be a JSON object"),
+        }),
+    }
+}
+
+/// Applies `desc` and `completed` from `body`, returns the changed task
+fn patch(
+    server: &Server,
+    mut params: Map<String, Value>,
+    body: &Map<String, Value>,
+) -> std::result::Result<Value, RpcError> {
+    for name in ["project", "desc", "completed"] {
+        if let Some(value) = body.get(name) {
+            params.insert(String::from(name), value.clone());
+        }
+    }
+
+    // one call, so both changes are saved together or not at all
+    server.call("edit", &params)
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(&value.replace('+', " ")))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+    use std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use super::{
+        handle, is_allowed_host, loopback_addrs, percent_decode, read_request, Request,
+        MAX_HEADERS, MAX_LINE,
+    };
+    use crate::{
+        rpc::Server,
+        storage::{MemoryStorage, Storage},
+        Project, PROJECT_FILE_NAME,
+    };
+
+    fn server() -> Server {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let mut root = Project::with_storage(
+            storage.clone(),
+            PathBuf::from("/p").join(PROJECT_FILE_NAME),
+            0,
+            String::from("my root"),
+            false,
+        );
+        root.add(String::from("first"), false).unwrap();
+        root.save().unwrap();
+
+        Server::with_storage(storage, Path::new("/p")).unwrap()
+    }
+
+    fn request(method: &str, target: &str, body: Option<Value>) -> Request {
+        Request {
+            method: method.to_string(),
+            target: target.to_string(),
+            content_type: body.as_ref().map(|_| String::from("application/json")),
+            body: body.map(|b| b.to_string().into_bytes()).unwrap_or_default(),
+        }
+    }
+
+    fn send(server: &Server, request: &Request) -> (u16, Value) {
+        let response = handle(server, request);
+        (
+            response.status,
+            serde_json::from_str(&response.body).unwrap(),
+        )
+    }
+
+    #[test]
+    fn endpoints() {
+        let server = server();
+
+        let page = handle(&server, &request("GET", "/", None));
+        assert_eq!(page.status, 200);
+        assert!(page.body.contains("<html"));
+
+        let (status, tree) = send(&server, &request("GET", "/api/tree", None));
+        assert_eq!(status, 200);
+        assert_eq!(tree["tasks"][0]["desc"], "first");
+
+        let (status, task) = send(
+            &server,
+            &request("POST", "/api/tasks", Some(json!({"desc": "second"}))),
+        );
+        assert_eq!(status, 201);
+        assert_eq!(task["index"], 1);
+
+        let (status, task) = send(
+            &server,
+            &request(
+                "PATCH",
+                "/api/tasks/1?project=my+root",
+                Some(json!({"desc": "renamed", "completed": true})),
+            ),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(
+            (&task["desc"], &task["completed"]),
+            (&json!("renamed"), &json!(true))
+        );
+
+        let (status, _) = send(
+            &server,
+            &request("DELETE", "/api/tasks/0?project=my%20root", None),
+        );
+        assert_eq!(status, 200);
+        let (_, tree) = send(&server, &request("GET", "/api/tree", None));
+        assert_eq!(tree["tasks"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        let server = server();
+        let status = |r: &Request| handle(&server, r).status;
+
+        assert_eq!(status(&request("GET", "/nope", None)), 404);
+        assert_eq!(status(&request("PUT", "/api/tree", None)), 405);
+        assert_eq!(status(&request("DELETE", "/api/tasks/42", None)), 404);
+        assert_eq!(
+            status(&request("PATCH", "/api/tasks/0", Some(json!({})))),
+            400
+        );
+        assert_eq!(
+            status(&request("GET", "/api/tree?project=nope", None)),
+            200,
+            "list ignores the project"
+        );
+
+        // no JSON content type, as sent by plain HTML forms of other sites
+        let mut form = request("POST", "/api/tasks", Some(json!({"desc": "x"})));
+        form.content_type = Some(String::from("text/plain"));
+        assert_eq!(status(&form), 400);
+
+        assert!(is_allowed_host("localhost:8080", "127.0.0.1"));
+        assert!(is_allowed_host("[::1]:8080", "127.0.0.1"));
+        assert!(is_allowed_host("192.168.1.2:8080", "192.168.1.2"));
+        assert!(!is_allowed_host("evil.example:8080", "127.0.0.1"));
+
+        assert!(loopback_addrs("127.0.0.1:8080").is_ok());
+        assert!(loopback_addrs("[::1]:8080").is_ok());
+        assert!(loopback_addrs("0.0.0.0:8080").is_err());
+        assert!(loopback_addrs("192.168.1.2:8080").is_err());
+
+        assert_eq!(percent_decode("a%20b%2"), "a b%2");
+    }
+
+    #[test]
+    fn limits() {
+        let read = |raw: String| read_request(raw.as_bytes(), "127.0.0.1").map_err(|r| r.status);
+
+        let request = read(String::from(
+            "POST /api/tasks HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{}",
+        ))
+        .unwrap();
+        assert_eq!(request.body, b"{}");
+
+        let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read(long_target), Err(431));
+
+        let long_header = format!(
+            "GET / HTTP/1.1\r\nHost: localhost\r\nX: {}\r\n\r\n",
+            "a".repeat(MAX_LINE)
+        );
+        assert_eq!(read(long_header), Err(431));
+
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+            "X: a\r\n".repeat(MAX_HEADERS)
+        );
+        assert_eq!(read(many_headers), Err(431));
+    }
+}